@@ -2,7 +2,15 @@
 // Also known as Karatsuba multiplication
 // See: https://en.wikipedia.org/wiki/Karatsuba_algorithm
 
-crate::entry_point!("karatsuba", main);
+use serde::Serialize;
+use std::fmt;
+
+crate::entry_point!("karatsuba", main, {
+    category: Introduction,
+    description: "Multiply two numbers with Karatsuba's divide and conquer",
+    datasets: [],
+    params: ["x y" => "numbers to multiply (default 1234567 9876543)"],
+});
 
 /*
 fn split(x: u64) -> (u64, u64) {
@@ -11,11 +19,25 @@ fn split(x: u64) -> (u64, u64) {
 }
 */
 
-fn main() -> () {
-    let x = 1234567;
-    let y = 9876543;
+// cargo run karatsuba [x y]
+fn main(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
+    let x = args.arg(0)?.unwrap_or(1234567);
+    let y = args.arg(1)?.unwrap_or(9876543);
     let z = karatsuba(x, y);
-    println!("{} * {} = {}", x, y, z);
+    Ok(Box::new(Product { x, y, z }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct Product {
+    pub x: u64,
+    pub y: u64,
+    pub z: u64,
+}
+
+impl fmt::Display for Product {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} * {} = {}", self.x, self.y, self.z)
+    }
 }
 
 pub fn karatsuba(x: u64, y: u64) -> u64 {
@@ -1,6 +1,13 @@
+use serde::Serialize;
+use std::fmt;
 use std::u128;
 
-crate::entry_point!("inversions", main);
+crate::entry_point!("inversions", main, {
+    category: Hw1,
+    description: "Count inversions in an array with merge sort",
+    datasets: ["IntegerArray.txt"],
+    params: ["--input FILE" => "whitespace separated integers (default data/IntegerArray.txt)"],
+});
 
 // Take the ownership to mutate!
 // See https://zulip.memorici.de/#narrow/stream/4-rust/topic/Vectors.2C.20slices.2C.20unsized.20types.20and.20fat.20pointers/near/59301
@@ -49,15 +56,17 @@ fn sort_and_count_inversions_do(xs: &mut [u64]) -> u128 {
     left_inversions + right_inversions + split_inversions
 }
 
-pub fn main() {
+pub fn main(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
     // Read the input into a vector of u64 integers.
-    // Input is in ./data/IntegerArray.txt
-    let input: Vec<u64> = std::fs::read_to_string("./data/IntegerArray.txt")
-        .expect("Something went wrong reading the file")
+    // Input is IntegerArray.txt in the data root unless --input says otherwise
+    let input: Vec<u64> = std::fs::read_to_string(args.input_or_dataset("IntegerArray.txt")?)?
         .split_whitespace()
-        .map(|x| x.parse::<u64>().unwrap())
-        .collect();
-    println!(
+        .map(|x| x.parse::<u64>())
+        .collect::<Result<_, _>>()?;
+    if input.is_empty() {
+        return Err("input is empty".into());
+    }
+    eprintln!(
         "Input length: {}; max: {}, min: {}, average: {}",
         input.len(),
         input.iter().max().unwrap(),
@@ -70,7 +79,20 @@ pub fn main() {
     // Note how we have declared input as immutable, but since we forfeit the ownership to the
     // underlying function and we can't use it anymore, the underlying function can declare its
     // argument as mutable.
-    println!("Inversions: {}", count_inversions(input));
+    Ok(Box::new(Inversions {
+        inversions: count_inversions(input),
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct Inversions {
+    pub inversions: u128,
+}
+
+impl fmt::Display for Inversions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Inversions: {}", self.inversions)
+    }
 }
 
 #[cfg(test)]
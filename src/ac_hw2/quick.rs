@@ -1,6 +1,13 @@
+use serde::Serialize;
+use std::fmt;
 use std::u128;
 
-crate::entry_point!("comparisons", main);
+crate::entry_point!("comparisons", main, {
+    category: Hw2,
+    description: "Count quicksort comparisons with first, last and median-of-three pivots",
+    datasets: ["QuickSort.txt"],
+    params: ["--input FILE" => "whitespace separated integers (default data/QuickSort.txt)"],
+});
 
 #[derive(Debug, Clone, Copy)]
 enum Pivot {
@@ -68,23 +75,36 @@ fn quick_sort_inner(input: &mut [u64], comparisons: &mut u128, pivot: Pivot) {
     quick_sort_inner(&mut input[last_smaller + 1..len], comparisons, pivot);
 }
 
-pub fn main() {
+pub fn main(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
     // Read the input into a vector of u64 integers.
-    // Input is in ./data/QuickSort.txt
-    let input: Vec<u64> = std::fs::read_to_string("./data/QuickSort.txt")
-        .expect("Something went wrong reading the file")
+    // Input is QuickSort.txt in the data root unless --input says otherwise
+    let input: Vec<u64> = std::fs::read_to_string(args.input_or_dataset("QuickSort.txt")?)?
         .split_whitespace()
-        .map(|x| x.parse::<u64>().unwrap())
-        .collect();
+        .map(|x| x.parse::<u64>())
+        .collect::<Result<_, _>>()?;
+
+    Ok(Box::new(Comparisons {
+        first: quick_sort(&mut input.clone(), Pivot::First),
+        last: quick_sort(&mut input.clone(), Pivot::Last),
+        median: quick_sort(&mut input.clone(), Pivot::Median),
+    }))
+}
 
-    let comparisons_first = quick_sort(&mut input.clone(), Pivot::First);
-    let comparisons_last = quick_sort(&mut input.clone(), Pivot::Last);
-    let comparisons_median = quick_sort(&mut input.clone(), Pivot::Median);
+#[derive(Debug, Serialize)]
+pub struct Comparisons {
+    pub first: u128,
+    pub last: u128,
+    pub median: u128,
+}
 
-    println!(
-        "Comparisons first: {}, last: {}, median: {}",
-        comparisons_first, comparisons_last, comparisons_median
-    );
+impl fmt::Display for Comparisons {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Comparisons first: {}, last: {}, median: {}",
+            self.first, self.last, self.median
+        )
+    }
 }
 
 #[cfg(test)]
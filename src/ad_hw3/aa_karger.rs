@@ -1,4 +1,23 @@
-crate::entry_point!("karger", main);
+crate::entry_point!("karger", main, {
+    category: Hw3,
+    description: "Minimum cut of an undirected graph with Karger's random contraction",
+    datasets: ["kargerMinCut.txt"],
+    params: [
+        "--input FILE" => "adjacency lists, one node per line (default data/kargerMinCut.txt)",
+        "--lenient" => "skip lines that don't parse, with a warning each",
+        "--seed N" => "seed the random contractions for a reproducible run",
+        "--iterations N" => "number of contraction trials (default n^2 ln n)",
+    ],
+});
+
+use crate::graph::biconnected::{biconnectivity, UndirectedView};
+use crate::graph::bipartite::{two_colour, Bipartition, OddCycle};
+use crate::graph::builder::Builder;
+use crate::graph::formats::{self, Number};
+use crate::graph::parse::{report, LoadError, Mode, ParseError, Records};
+use fxhash::FxHashMap as HashMap;
+use serde::Serialize;
+use std::fmt;
 
 trait MinCuttable {
     fn min_cut(&mut self, rng: &mut impl rand::Rng) -> (usize, Vec<usize>, Vec<usize>);
@@ -24,20 +43,75 @@ impl Graph {
     // 1	37	79	164	155	32	87	39	113	15	18	78	175	140	200	4	160	97	191	100	91	20	69	198	196
     // 2	123	134	10	141	13	12	43	47	3	177	101	179	77	182	117	116	36	103	51	154	162	128	30
     // 3	48	123	134	109	41	17	159	49	136	16	130	141	29	176	2	190	66	153	157	70	114	65	173	104	194	54
-    fn load_from_file(&mut self, path: &str) {
-        use std::fs::File;
-        use std::io::BufRead;
-        let file = File::open(path).unwrap();
-        let reader = std::io::BufReader::new(file);
-        for (id, line) in reader.lines().enumerate() {
-            let line = line.unwrap();
-            let mut edges = Vec::new();
-            for edge in line.split_whitespace().skip(1) {
-                // !!! We go from 1-indexed to 0-indexed here !!!
-                edges.push(edge.parse::<usize>().unwrap() - 1);
+    //
+    // Each line starts with the node it's about, so lines can come in any order. A bad line is
+    // an error, or with `Mode::Lenient` a warning, and the node gets no edges from it.
+    fn load_from_file(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+        mode: Mode,
+    ) -> Result<Vec<ParseError>, LoadError> {
+        self.load_records(Records::open(path, mode, formats::adjacency_line)?)
+    }
+
+    // The same from lines that come from anywhere
+    fn load_records(
+        &mut self,
+        records: Records<(Number, Vec<Number>)>,
+    ) -> Result<Vec<ParseError>, LoadError> {
+        let (lines, warnings) = records.read_all()?;
+        // !!! We go from 1-indexed to 0-indexed here !!!
+        for (id, edges) in lines {
+            self.add_list(id.index(), edges.into_iter().map(Number::index).collect());
+        }
+        Ok(warnings)
+    }
+
+    // 0-indexed; any node mentioned that isn't there yet is added without edges
+    fn add_list(&mut self, id: usize, edges: Vec<usize>) {
+        let needed = edges.iter().copied().chain(Some(id)).max().unwrap() + 1;
+        while self.nodes.len() < needed {
+            let id = self.nodes.len();
+            self.nodes.push(Node {
+                id,
+                edges: Vec::new(),
+            });
+        }
+        self.nodes[id].edges.extend(edges);
+    }
+
+    // Contraction needs every edge listed at both ends
+    fn check_lists(&self) -> Result<(), String> {
+        let mut counts: HashMap<(usize, usize), isize> = HashMap::default();
+        for node in &self.nodes {
+            for &to in &node.edges {
+                *counts
+                    .entry((node.id.min(to), node.id.max(to)))
+                    .or_default() += if node.id < to { 1 } else { -1 };
             }
-            self.nodes.push(Node { id, edges });
         }
+        // The first pair in order, so the same file always gets the same complaint
+        let uneven = counts.into_iter().filter(|&(_, count)| count != 0);
+        match uneven.map(|(pair, _)| pair).min() {
+            Some((a, b)) => Err(format!(
+                "nodes {} and {} don't list each other equally often",
+                a + 1,
+                b + 1
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // Adjacency lists name every edge at both ends, parallel edges included, which is just
+    // what the view wants. Ids stay 0-indexed.
+    fn undirected_view(&self) -> UndirectedView<usize> {
+        let ids = self.nodes.iter().map(|node| node.id).collect();
+        let lists: Vec<Vec<usize>> = self.nodes.iter().map(|node| node.edges.clone()).collect();
+        UndirectedView::from_symmetric_lists(ids, &lists)
+    }
+
+    fn two_colour(&self) -> Result<Bipartition<usize>, OddCycle<usize>> {
+        two_colour(&self.undirected_view())
     }
 }
 
@@ -125,14 +199,82 @@ impl MinCuttable for Graph {
     }
 }
 
-fn main() {
+// Numbered from 1 like the data files. Every edge goes in its source's list, so an edge has to
+// be `a -- b` to be listed at both ends the way `check_lists` wants.
+impl From<Builder<usize>> for Graph {
+    fn from(builder: Builder<usize>) -> Self {
+        let mut graph = Graph::new();
+        let index = |id: usize| id.checked_sub(1).expect("nodes are numbered from 1");
+        for id in builder.nodes {
+            graph.add_list(index(id), Vec::new());
+        }
+        for (from, to, ()) in builder.edges {
+            graph.add_list(index(from), vec![index(to)]);
+        }
+        graph
+    }
+}
+
+fn main(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
+    use rand::{rngs::StdRng, SeedableRng};
     let mut graph = Graph::new();
-    graph.load_from_file("data/kargerMinCut.txt");
+    let warnings =
+        graph.load_from_file(args.input_or_dataset("kargerMinCut.txt")?, args.load_mode())?;
+    report(&warnings);
     let n = graph.nodes.len();
+    if n < 2 {
+        return Err(format!("need at least 2 nodes to cut, got {}", n).into());
+    }
+    graph.check_lists()?;
+    // Nothing to do with the cut, but a cheap look at what the file holds
+    let edges: usize = graph
+        .nodes
+        .iter()
+        .map(|node| node.edges.len())
+        .sum::<usize>()
+        / 2;
+    match graph.two_colour() {
+        Ok(colours) => eprintln!(
+            "{} nodes, {} edges, bipartite: {} + {}",
+            n,
+            edges,
+            colours.left.len(),
+            colours.right.len()
+        ),
+        Err(cycle) => eprintln!(
+            "{} nodes, {} edges, not bipartite: odd cycle of {}",
+            n,
+            edges,
+            cycle.nodes.len()
+        ),
+    }
+    // Two pieces with nothing between them are already cut, by no edges at all. Contraction
+    // would only find that out by ending up with an empty cut, or get stuck on a node without
+    // any.
+    let view = graph.undirected_view();
+    if !view.is_connected() {
+        eprintln!("the graph isn't connected, skipping the random trials");
+        return Ok(Box::new(MinCut { min_cut: 0 }));
+    }
+    // A bridge is a cut of one edge, and no cut of a connected graph is smaller, so there's
+    // nothing left for the random trials to find.
+    if let Some((a, b)) = biconnectivity(&view).bridges.first() {
+        eprintln!(
+            "{} - {} is a bridge, skipping the random trials",
+            a + 1,
+            b + 1
+        );
+        return Ok(Box::new(MinCut { min_cut: 1 }));
+    }
     // To be (1 - (1 / n)) certain that we find the min cut, we need to run the algorithm n^2 * ln(n) times.
+    // --iterations overrides that when we're in a hurry.
     let ln_ceil = (n as f64).ln().ceil() as usize;
-    let iterations_to_certainty = (n * n * ln_ceil) as usize;
-    let mut rng = rand::thread_rng();
+    let iterations_to_certainty = args.iterations.unwrap_or(n * n * ln_ceil);
+    // --seed makes the run reproducible.
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
     let mut min_cut = usize::MAX;
     for i in 0..iterations_to_certainty {
         let (cut, _, _) = graph.clone().min_cut(&mut rng);
@@ -145,7 +287,7 @@ fn main() {
             iterations_to_certainty
         ); */
         if cut == 0 {
-            panic!("Impossible happened!");
+            return Err("Impossible happened! Found a cut of size 0".into());
         }
         if cut < min_cut {
             min_cut = cut;
@@ -154,7 +296,7 @@ fn main() {
             break;
         }
         if i % 1_000 == 0 {
-            println!(
+            eprintln!(
                 "Iteration {} out of {} ({}%) :: min cut: {}",
                 i,
                 iterations_to_certainty,
@@ -163,41 +305,82 @@ fn main() {
             );
         }
     }
-    println!("min cut: {}", min_cut);
+    Ok(Box::new(MinCut { min_cut }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct MinCut {
+    pub min_cut: usize,
+}
+
+impl fmt::Display for MinCut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "min cut: {}", self.min_cut)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::TempFile;
+
+    // `text` as the lines of a kargerMinCut.txt-style file called `x`
+    fn lines(text: &'static str, mode: Mode) -> Records<(Number, Vec<Number>)> {
+        let file = std::path::Path::new("x");
+        Records::new(file, text.as_bytes(), mode, formats::adjacency_line)
+    }
+
     #[test]
     fn triangle() {
-        let mut graph = Graph::new();
-        graph.nodes.push(Node {
-            id: 0,
-            edges: vec![1, 2],
-        });
-        graph.nodes.push(Node {
-            id: 1,
-            edges: vec![0, 2],
-        });
-        graph.nodes.push(Node {
-            id: 2,
-            edges: vec![0, 1],
-        });
+        let graph: Graph = crate::graph! { 1 -- 2, 3; 2 -- 3 };
+        assert_eq!(graph.nodes[1].edges, vec![0, 2]);
         let mut rng = rand::thread_rng();
         let (min, a, b) = graph.clone().min_cut(&mut rng);
         assert_eq!(min, 2);
         assert!((a.len() == 1 && b.len() == 2) || (a.len() == 2 && b.len() == 1));
+        let path = crate::require_dataset!("kargerTriangle.txt");
         let mut triangle_from_file = Graph::new();
-        triangle_from_file.load_from_file("data/kargerTriangle.txt");
+        triangle_from_file
+            .load_from_file(path, Mode::Strict)
+            .unwrap();
         // triangle_from_file should be the same as graph:
         assert_eq!(triangle_from_file, graph.clone());
     }
 
+    #[test]
+    fn two_colouring() {
+        // The triangle is as small as odd cycles get
+        let triangle: Graph = crate::graph! { 1 -- 2, 3; 2 -- 3 };
+        assert_eq!(triangle.two_colour().unwrap_err().nodes.len(), 3);
+
+        // A square 1-2-3-4 with 2 - 3 doubled: still bipartite. Ids are 0-indexed inside.
+        let mut graph: Graph = crate::graph! { 1 -- 2, 4; 2 -- 3, 3; 3 -- 4 };
+        assert_eq!(graph.nodes[2].edges, vec![1, 1, 3]);
+        let colours = graph.two_colour().unwrap();
+        assert_eq!((colours.left, colours.right), (vec![0, 2], vec![1, 3]));
+
+        // Cutting across the square with 0 - 2 makes triangles
+        graph.nodes[0].edges.push(2);
+        graph.nodes[2].edges.push(0);
+        let cycle = graph.two_colour().unwrap_err();
+        assert_eq!(cycle.nodes.len(), 3);
+        for (i, &a) in cycle.nodes.iter().enumerate() {
+            let b = cycle.nodes[(i + 1) % 3];
+            assert!(graph.nodes[a].edges.contains(&b));
+        }
+
+        let path = crate::require_dataset!("kargerMinCut.txt");
+        let mut graph = Graph::new();
+        graph.load_from_file(path, Mode::Strict).unwrap();
+        let cycle = graph.two_colour().unwrap_err();
+        assert_eq!(cycle.nodes.len() % 2, 1);
+    }
+
     #[test]
     fn two_envelopes() {
+        let path = crate::require_dataset!("kargerEnvelopes.txt");
         let mut graph = Graph::new();
-        graph.load_from_file("data/kargerEnvelopes.txt");
+        graph.load_from_file(path, Mode::Strict).unwrap();
         let mut rng = rand::thread_rng();
         let mut min = graph.nodes.len() * graph.nodes.len();
         let mut min_a = Vec::new();
@@ -226,4 +409,97 @@ mod tests {
 
         assert_eq!(min, 2);
     }
+
+    #[test]
+    fn bridges_answer_right_away() {
+        // Two triangles 1-2-3 and 4-5-6 joined by 3 - 4, 1-indexed like the data files
+        let text = "1 2 3\n2 1 3\n3 1 2 4\n4 3 5 6\n5 4 6\n6 4 5\n";
+        let mut graph = Graph::new();
+        graph.load_records(lines(text, Mode::Strict)).unwrap();
+        let result = biconnectivity(&graph.undirected_view());
+        assert_eq!(result.bridges, vec![(2, 3)]);
+        let mut cuts = result.articulation_points.clone();
+        cuts.sort_unstable();
+        assert_eq!(cuts, vec![2, 3]);
+
+        // Way fewer trials than it would take to find the cut by chance, if it ran any
+        let file = TempFile::new("karger-bridge", text);
+        let args = crate::cli::Args {
+            input: Some(file.path.clone()),
+            iterations: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(main(&args).unwrap().to_string(), "min cut: 1");
+
+        // Doubling the bridge makes it two edges: no bridge, and a min cut of 2
+        graph.nodes[2].edges.push(3);
+        graph.nodes[3].edges.push(2);
+        assert!(biconnectivity(&graph.undirected_view()).bridges.is_empty());
+        let (cut, _, _) = graph.min_cut(&mut rand::thread_rng());
+        assert!(cut >= 2);
+
+        // The envelopes' min cut is 2, so they have no bridge either
+        let path = crate::require_dataset!("kargerEnvelopes.txt");
+        let mut envelopes = Graph::new();
+        envelopes.load_from_file(path, Mode::Strict).unwrap();
+        assert!(biconnectivity(&envelopes.undirected_view())
+            .bridges
+            .is_empty());
+    }
+
+    #[test]
+    fn disconnected_graphs_are_cut_by_nothing() {
+        // 1 - 2 is a bridge, but it's in a piece of its own next to the triangle 3-4-5
+        let file = TempFile::new("karger-apart", "1 2\n2 1\n3 4 5\n4 3 5\n5 3 4\n");
+        let args = crate::cli::Args {
+            input: Some(file.path.clone()),
+            iterations: Some(0),
+            ..Default::default()
+        };
+        let bridged = main(&args).unwrap().to_string();
+        // Without the bridge: two triangles, which contraction alone would trip over
+        file.write("1 2 3\n2 1 3\n3 1 2\n4 5 6\n5 4 6\n6 4 5\n");
+        let answer = main(&crate::cli::Args {
+            iterations: Some(10),
+            ..args.clone()
+        })
+        .unwrap()
+        .to_string();
+        // A node on its own line with no edges is a piece too
+        file.write("1 2\n2 1\n3\n");
+        let isolated = main(&args).map(|answer| answer.to_string());
+        assert_eq!(bridged, "min cut: 0");
+        assert_eq!(answer, "min cut: 0");
+        assert_eq!(isolated.unwrap(), "min cut: 0");
+    }
+
+    #[test]
+    fn hand_edited_lists() {
+        // A typo on line 2; skipping it leaves 1 - 2 listed at one end only
+        let text = "1 2 3\n2 1 3x\n3 1 2\n";
+        let mut graph = Graph::new();
+        match graph.load_records(lines(text, Mode::Strict)) {
+            Err(LoadError::Parse(e)) => {
+                assert_eq!((e.line, e.column, e.token.as_str()), (2, 5, "3x"))
+            }
+            other => panic!("{:?}", other),
+        }
+        let mut graph = Graph::new();
+        let warnings = graph.load_records(lines(text, Mode::Lenient)).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(graph.nodes[1].edges, Vec::<usize>::new());
+        let uneven = "nodes 1 and 2 don't list each other equally often";
+        assert_eq!(graph.check_lists(), Err(uneven.to_string()));
+
+        let file = TempFile::new("karger-typo", text);
+        let args = crate::cli::Args {
+            input: Some(file.path.clone()),
+            lenient: true,
+            ..Default::default()
+        };
+        match main(&args) {
+            Err(e) => assert_eq!(e.to_string(), uneven),
+            Ok(answer) => panic!("{}", answer),
+        }
+    }
 }
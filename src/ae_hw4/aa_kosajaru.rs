@@ -1,56 +1,92 @@
-crate::entry_point!("kosajaru", main);
-use crate::graph::{dfs_finish_ord, irrel, Graph, Node};
+crate::entry_point!("kosajaru", main, {
+    category: Hw4,
+    description: "Sizes of the five largest strongly connected components (Kosaraju)",
+    datasets: ["SCC.txt"],
+    params: [
+        "--input FILE" => "directed edges, one `from to` pair per line (default data/SCC.txt)",
+        "--lenient" => "skip lines that don't parse, with a warning each",
+        "kosaraju|tarjan" => "which algorithm finds the components (default kosaraju)",
+    ],
+});
+use crate::graph::csr::CsrGraph;
+use crate::graph::parse::report;
+use crate::graph::scc::tarjan;
+use crate::graph::{dfs_finish_ord, irrel, Adjacency, Forward, Graph, Neighbours, Reverse};
 use fxhash::FxHashMap as HashMap;
 use fxhash::FxHashSet as HashSet;
+use serde::Serialize;
 use std::collections::VecDeque;
-use std::hash::Hash;
-use std::str::FromStr;
+use std::fmt;
 
-pub fn main() {
-    let mut graph: Graph<usize> = Graph::new();
-    graph.load_from_directed_edges_file("data/SCC.txt");
+pub fn main(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
+    // 875,714 nodes: the flat representation saves most of the memory and hashing
+    let (graph, warnings): (CsrGraph<usize>, _) = CsrGraph::load_from_directed_edges_file(
+        args.input_or_dataset("SCC.txt")?,
+        args.load_mode(),
+    )?;
+    report(&warnings);
+    if graph.node_count() == 0 {
+        return Err("graph is empty".into());
+    }
 
-    let sccs = kosaraju(&mut graph);
+    let mut outcome_sorted = match args.arg::<String>(0)?.as_deref() {
+        None | Some("kosaraju") => kosaraju(&graph)
+            .iter()
+            .map(|x| x.nodes.len())
+            .collect::<Vec<usize>>(),
+        // One pass instead of two, same answer
+        Some("tarjan") => tarjan(&graph).sizes,
+        Some(other) => return Err(format!("unknown SCC algorithm {:?}", other).into()),
+    };
     // Get top 5 sized SCCs
-    let mut outcome_sorted = sccs.iter().map(|x| x.nodes.len()).collect::<Vec<usize>>();
     outcome_sorted.sort();
     let outcome = outcome_sorted
         .into_iter()
         .rev()
         .take(5)
         .collect::<Vec<usize>>();
-    // Print their sizes
-    println!("{:?}", outcome);
+    Ok(Box::new(LargestSccs { sizes: outcome }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct LargestSccs {
+    pub sizes: Vec<usize>,
+}
+
+impl fmt::Display for LargestSccs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.sizes)
+    }
 }
 
-pub fn kosaraju(graph: &mut Graph<usize>) -> Vec<Graph<usize>> {
-    let trajectory1: VecDeque<usize> = graph.nodes.keys().cloned().collect();
-    let (_, topo_sort1) = dfs_finish_ord(graph, &trajectory1, |x| x.inverse_edges.clone(), irrel);
+pub fn kosaraju<G: Adjacency + ?Sized>(graph: &G) -> Vec<Graph<G::Id>> {
+    let trajectory1: VecDeque<G::Id> = graph.node_ids().into_iter().collect();
+    let (_, topo_sort1) = dfs_finish_ord(graph, &trajectory1, Reverse, irrel);
     // Trajectory 2 is topo_sort1 from lowest vertex to highest
     // dbg!(&topo_sort1);
     let sort_topo1 = topo_sort1
         .iter()
         .map(|(k, v)| (v.clone(), k))
-        .collect::<HashMap<i64, &usize>>();
+        .collect::<HashMap<i64, &G::Id>>();
     let mut trajectory2 = VecDeque::new();
     for i in 0..sort_topo1.len() {
-        let nid: usize = *sort_topo1.get(&(i as i64)).unwrap().clone();
+        let nid: G::Id = (*sort_topo1.get(&(i as i64)).unwrap()).clone();
         trajectory2.push_back(nid);
     }
     // dbg!(&graph);
     // dbg!(&trajectory2);
-    let sccs = dfs_scc(graph, &trajectory2, |x| x.edges.clone());
+    let sccs = dfs_scc(graph, &trajectory2, Forward);
     sccs
 }
 
-pub fn dfs_scc<T>(
-    graph: &mut Graph<T>,
-    trajectory: &VecDeque<T>,
-    neighbours: fn(&Node<T>) -> Vec<T>,
-) -> Vec<Graph<T>>
+pub fn dfs_scc<G, N>(
+    graph: &G,
+    trajectory: &VecDeque<G::Id>,
+    mut neighbours: N,
+) -> Vec<Graph<G::Id>>
 where
-    T: Clone + Eq + Hash + FromStr + std::fmt::Debug,
-    <T as FromStr>::Err: std::fmt::Debug,
+    G: Adjacency + ?Sized,
+    N: Neighbours<G>,
 {
     let mut finish_times = HashMap::default();
     let mut time = 0;
@@ -84,12 +120,9 @@ where
             seen.insert(node.clone());
             // eprintln!("Pushing node {:?}", node);
             stack.push_back(node.clone());
-            let node = graph.nodes.get(&node).unwrap();
-            let neighbours = neighbours(node);
-            for neighbour in neighbours {
-                let n = neighbour.clone();
-                if !(&neighbour == &node.id) && !seen.contains(&neighbour) {
-                    stack.push_back(n);
+            for neighbour in neighbours.neighbours(graph, &node) {
+                if !(neighbour == &node) && !seen.contains(neighbour) {
+                    stack.push_back(neighbour.clone());
                 }
             }
         } else {
@@ -105,6 +138,18 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::graph::parse::Mode;
+    use crate::graph::scc::{components_from_graphs, same_partition, Condensation};
+
+    // Both algorithms split the graph the same way, and the condensation is acyclic
+    fn agrees_with_tarjan<G: Adjacency + ?Sized>(graph: &G, sccs: &[Graph<G::Id>]) {
+        let from_tarjan = tarjan(graph);
+        assert!(same_partition(&components_from_graphs(sccs), &from_tarjan));
+        let condensation = Condensation::new(graph);
+        for node in condensation.dag.nodes.values() {
+            assert!(node.edges.iter().all(|&to| to < node.id));
+        }
+    }
 
     #[test]
     fn test_orion_is_scc_2() {
@@ -118,21 +163,8 @@ mod tests {
          *              `-- 5
          *
          */
-        let mut graph: Graph<usize> = Graph::new();
-        graph.add_edge(2, 1);
-        graph.add_edge(1, 3);
-        graph.add_edge(3, 4);
-        graph.add_edge(4, 2);
-        graph.add_edge(5, 4);
-        let mut loopy: Graph<usize> = Graph::new();
-        loopy.add_edge(2, 1);
-        loopy.add_edge(1, 3);
-        loopy.add_edge(3, 4);
-        loopy.add_edge(4, 2);
-        let mut taily: Graph<usize> = Graph::new();
-        taily.add_edge(5, 4);
-        //
-        let sccs = kosaraju(&mut graph);
+        let graph: Graph<usize> = crate::graph! { 2 -> 1; 1 -> 3; 3 -> 4; 4 -> 2; 5 -> 4 };
+        let sccs = kosaraju(&graph);
         let mut outcome_sorted = sccs.iter().map(|x| x.nodes.len()).collect::<Vec<usize>>();
         outcome_sorted.sort();
         let outcome = outcome_sorted.into_iter().rev().collect::<Vec<usize>>();
@@ -158,28 +190,14 @@ mod tests {
          *    4<---2----------->10
          *
          */
-        let mut graph: Graph<usize> = Graph::new();
-        graph.add_edge(1, 3);
-        graph.add_edge(2, 4);
-        graph.add_edge(2, 10);
-        graph.add_edge(3, 5);
-        graph.add_edge(3, 11);
-        graph.add_edge(4, 7);
-        graph.add_edge(5, 1);
-        graph.add_edge(5, 7);
-        graph.add_edge(5, 9);
-        graph.add_edge(6, 10);
-        graph.add_edge(7, 9);
-        graph.add_edge(8, 6);
-        graph.add_edge(9, 2);
-        graph.add_edge(9, 4);
-        graph.add_edge(9, 8);
-        graph.add_edge(10, 8);
-        graph.add_edge(11, 6);
-        graph.add_edge(11, 8);
-        let sccs = kosaraju(&mut graph);
+        let graph: Graph<usize> = crate::graph! {
+            1 -> 3; 2 -> 4, 10; 3 -> 5, 11; 4 -> 7; 5 -> 1, 7, 9; 6 -> 10;
+            7 -> 9; 8 -> 6; 9 -> 2, 4, 8; 10 -> 8; 11 -> 6, 8
+        };
+        let sccs = kosaraju(&graph);
         // dbg!(&sccs);
         assert_eq!(sccs.len(), 4);
+        agrees_with_tarjan(&graph, &sccs);
         let mut outcome_sorted = sccs.iter().map(|x| x.nodes.len()).collect::<Vec<usize>>();
         outcome_sorted.sort();
         assert_eq!(outcome_sorted, vec![1, 3, 3, 4]);
@@ -197,10 +215,8 @@ mod tests {
          *
          *
          */
-        let mut graph: Graph<usize> = Graph::new();
-        graph.add_edge(1, 2);
-        graph.add_edge(2, 1);
-        let sccs = kosaraju(&mut graph);
+        let graph: Graph<usize> = crate::graph! { 1 -- 2 };
+        let sccs = kosaraju(&graph);
         assert_eq!(sccs.len(), 1);
         assert_eq!(sccs[0].nodes.len(), 2);
     }
@@ -217,17 +233,21 @@ mod tests {
          *                          \
          *                          `--> 5
          */
-        let mut graph: Graph<usize> = Graph::new();
-        graph.add_edge(1, 2);
-        graph.add_edge(2, 3);
-        graph.add_edge(3, 1);
-        graph.add_edge(1, 3);
-        graph.add_edge(3, 4);
-        graph.add_edge(4, 3);
-        graph.add_edge(4, 4);
-        graph.add_edge(4, 5);
-        graph.add_edge(5, 4);
-        let sccs = kosaraju(&mut graph);
+        let graph: Graph<usize> = crate::graph! { 1 -> 2; 2 -> 3; 1 -- 3; 3 -- 4; 4 -> 4; 4 -- 5 };
+        let sccs = kosaraju(&graph);
         assert_eq!(sccs.len(), 1);
+        agrees_with_tarjan(&graph, &sccs);
+    }
+
+    #[test]
+    fn test_scc_txt_tarjan() {
+        let path = crate::require_dataset!("SCC.txt");
+        let (graph, _): (CsrGraph<usize>, _) =
+            CsrGraph::load_from_directed_edges_file(path, Mode::Strict).unwrap();
+        let sccs = kosaraju(&graph);
+        agrees_with_tarjan(&graph, &sccs);
+        let mut sizes = tarjan(&graph).sizes;
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(&sizes[..5], &[434821, 968, 459, 313, 211]);
     }
 }
@@ -1,9 +1,14 @@
-crate::entry_point!("kosyak", main);
+crate::entry_point!("kosyak", main, {
+    category: Hw4,
+    description: "Kosaraju on a small hand-drawn graph that used to trip it up",
+    datasets: [],
+    params: [],
+});
 
-use crate::aa_kosajaru::kosaraju;
+use crate::aa_kosajaru::{kosaraju, LargestSccs};
 use crate::graph::Graph;
 
-pub fn main() {
+pub fn main(_args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
     /*
      *
      *   ,-------------,
@@ -24,7 +29,9 @@ pub fn main() {
     graph.add_edge(4, 4);
     graph.add_edge(4, 5);
     graph.add_edge(5, 4);
-    let sccs = kosaraju(&mut graph);
-    dbg!(&sccs);
+    let sccs = kosaraju(&graph);
     assert_eq!(sccs.len(), 1);
+    Ok(Box::new(LargestSccs {
+        sizes: sccs.iter().map(|scc| scc.nodes.len()).collect(),
+    }))
 }
@@ -0,0 +1,83 @@
+crate::entry_point!("hops", main, {
+    category: Hw4,
+    description: "Hop-count distances from some nodes of the SCC graph (breadth-first search)",
+    datasets: ["SCC.txt"],
+    params: [
+        "--input FILE" => "directed edges, one `from to` pair per line (default data/SCC.txt)",
+        "--lenient" => "skip lines that don't parse, with a warning each",
+        "reverse" => "follow edges backwards: distances to the sources instead of from them",
+        "SOURCE..." => "nodes to start from, all at once (default 1)",
+    ],
+});
+
+use crate::graph::bfs::bfs;
+use crate::graph::csr::CsrGraph;
+use crate::graph::parse::report;
+use crate::graph::{Forward, Reverse};
+use serde::Serialize;
+use std::fmt;
+
+// cargo run hops [reverse] [SOURCE...]
+pub fn main(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
+    let (graph, warnings): (CsrGraph<usize>, _) = CsrGraph::load_from_directed_edges_file(
+        args.input_or_dataset("SCC.txt")?,
+        args.load_mode(),
+    )?;
+    report(&warnings);
+    let reverse = args.positional.first().map(String::as_str) == Some("reverse");
+    let mut sources = Vec::new();
+    for arg in &args.positional[reverse as usize..] {
+        let id: usize = arg.parse().map_err(|_| format!("bad node id {:?}", arg))?;
+        sources.push(
+            graph
+                .index_of(&id)
+                .ok_or_else(|| format!("node {} is not in the graph", id))?,
+        );
+    }
+    if sources.is_empty() {
+        sources.push(graph.index_of(&1).ok_or("node 1 is not in the graph")?);
+    }
+    let result = if reverse {
+        bfs(&graph, sources, Reverse)
+    } else {
+        bfs(&graph, sources, Forward)
+    };
+    let farthest = result.layers.last().and_then(|l| l.first()).copied();
+    Ok(Box::new(Hops {
+        reached: result.distance.len(),
+        layer_sizes: result.layers.iter().map(Vec::len).collect(),
+        farthest: farthest.map(|i| *graph.id(i)),
+        path: farthest
+            .and_then(|i| result.path_to(&i))
+            .map(|mut path| {
+                if reverse {
+                    // Along the edges, into the source
+                    path.reverse();
+                }
+                path.iter().map(|&i| *graph.id(i)).collect()
+            })
+            .unwrap_or_default(),
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct Hops {
+    pub reached: usize,
+    pub layer_sizes: Vec<usize>,
+    pub farthest: Option<usize>,
+    // Along the edges: from a source to `farthest`, or from `farthest` to a source in reverse
+    pub path: Vec<usize>,
+}
+
+impl fmt::Display for Hops {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "reached {} nodes in {} hops",
+            self.reached,
+            self.layer_sizes.len().saturating_sub(1)
+        )?;
+        writeln!(f, "layer sizes {:?}", self.layer_sizes)?;
+        write!(f, "a farthest path {:?}", self.path)
+    }
+}
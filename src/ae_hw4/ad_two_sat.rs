@@ -0,0 +1,417 @@
+crate::entry_point!("two-sat", main, {
+    category: Hw4,
+    description: "Satisfiability of 2-SAT clauses from the SCCs of the implication graph",
+    datasets: [],
+    params: [
+        "--input FILE" => "variable count, then one clause of two signed literals per line (default: a small built-in schedule)",
+        "--lenient" => "skip lines that don't parse, with a warning each",
+    ],
+});
+
+use crate::graph::bfs::bfs;
+use crate::graph::parse::{report, Line, LoadError, Mode, ParseError, Records};
+use crate::graph::scc::{tarjan, Components};
+use crate::graph::{Forward, Graph};
+use serde::Serialize;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+// Four talks, each in the morning (true) or the afternoon (false). Talks 1 and 2 share a
+// speaker, so they go in different slots; talk 1's speaker arrives at noon; talks 2 and 3
+// can't both have the morning room; and talk 4 builds on talk 3, so it's not in the morning
+// unless talk 3 is.
+const SCHEDULE: &str = "4\n1 2\n-1 -2\n-1 -1\n-2 -3\n3 -4\n";
+
+/// Clauses `a ∨ b` over variables `1..=variables`; literal `-x` is "not x".
+///
+/// ```text
+/// 3
+/// 1 -2
+/// -1 3
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TwoSat {
+    pub variables: usize,
+    pub clauses: Vec<(i64, i64)>,
+}
+
+// A non-blank line of a clause file: its one or two numbers, each with its column. The first
+// line should be the variable count and every other one a clause; which one a line should be,
+// and whether its literals are in range, depends on the lines before it, so `TwoSat::read`
+// checks that.
+struct ClauseLine {
+    line: usize,
+    numbers: Vec<(i64, usize)>,
+}
+
+fn clause_line(line: &Line<'_>) -> Result<ClauseLine, ParseError> {
+    let mut tokens = line.tokens();
+    let mut numbers = Vec::new();
+    for token in tokens.by_ref().take(2) {
+        numbers.push((line.parse(Some(token), "a number")?, token.column));
+    }
+    line.end(tokens)?;
+    Ok(ClauseLine {
+        line: line.number,
+        numbers,
+    })
+}
+
+/// Why there's no assignment: `cycle` goes `variable => ... => -variable => ... => variable`
+/// along implications, so whichever value the variable gets, the clauses force the other one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Conflict {
+    pub variable: i64,
+    pub cycle: Vec<i64>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} and {} imply each other:",
+            self.variable, -self.variable
+        )?;
+        for (i, literal) in self.cycle.iter().enumerate() {
+            write!(f, "{}{}", if i == 0 { " " } else { " => " }, literal)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+/// Values of variables `1..=n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    values: Vec<bool>,
+}
+
+impl Assignment {
+    // Whether `literal` comes out true: `value(-3)` is `!value(3)`
+    pub fn value(&self, literal: i64) -> bool {
+        self.values[literal.unsigned_abs() as usize - 1] == (literal > 0)
+    }
+
+    pub fn satisfies(&self, sat: &TwoSat) -> bool {
+        sat.clauses
+            .iter()
+            .all(|&(a, b)| self.value(a) || self.value(b))
+    }
+
+    // One literal per variable, `x` if it's true and `-x` if it's false
+    pub fn literals(&self) -> Vec<i64> {
+        (1..=self.values.len() as i64)
+            .map(|x| if self.value(x) { x } else { -x })
+            .collect()
+    }
+}
+
+impl TwoSat {
+    // Strict, for text at hand; messages call it `<clauses>`
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let file = Path::new("<clauses>");
+        let text = io::Cursor::new(text.to_string());
+        let records = Records::new(file, text, Mode::Strict, clause_line);
+        match TwoSat::read(file, records, Mode::Strict) {
+            Ok((sat, _)) => Ok(sat),
+            Err(LoadError::Parse(e)) => Err(e),
+            Err(LoadError::Io { error, .. }) => unreachable!("reading a String failed: {}", error),
+        }
+    }
+
+    // A bad line is an error, or with `Mode::Lenient` a warning in the second half of the
+    // result, and the clause is left out
+    pub fn load_from_file(
+        path: impl AsRef<Path>,
+        mode: Mode,
+    ) -> Result<(Self, Vec<ParseError>), LoadError> {
+        let path = path.as_ref();
+        TwoSat::read(path, Records::open(path, mode, clause_line)?, mode)
+    }
+
+    fn read(
+        file: &Path,
+        mut records: Records<ClauseLine>,
+        mode: Mode,
+    ) -> Result<(Self, Vec<ParseError>), LoadError> {
+        let mut variables = None;
+        let mut clauses = Vec::new();
+        let mut skipped = Vec::new();
+        for ClauseLine { line, numbers } in &mut records {
+            let error = |(number, column): (i64, usize), message: String| {
+                ParseError::new(file, line, column, &number.to_string(), message)
+            };
+            let problem = match (variables, &numbers[..]) {
+                (None, &[count]) if count.0 >= 0 => {
+                    variables = Some(count.0 as usize);
+                    None
+                }
+                (None, &[first, ..]) => Some(error(first, "expected the variable count".into())),
+                (Some(_), &[only]) => Some(error(only, "expected two literals".into())),
+                (Some(n), &[a, b]) => {
+                    let bad = |&(x, _): &(i64, usize)| x == 0 || x.unsigned_abs() as usize > n;
+                    match [a, b].iter().copied().find(bad) {
+                        Some(literal) => Some(error(
+                            literal,
+                            format!("expected a literal of variables 1 to {}", n),
+                        )),
+                        None => {
+                            clauses.push((a.0, b.0));
+                            None
+                        }
+                    }
+                }
+                _ => unreachable!("`clause_line` reads one or two numbers"),
+            };
+            match problem {
+                Some(e) if mode == Mode::Strict => return Err(e.into()),
+                Some(e) => skipped.push(e),
+                None => {}
+            }
+        }
+        let mut warnings = records.finish()?;
+        warnings.extend(skipped);
+        warnings.sort_by_key(|e| e.line);
+        let variables =
+            variables.ok_or_else(|| ParseError::whole_file(file, "no variable count"))?;
+        Ok((TwoSat { variables, clauses }, warnings))
+    }
+
+    // Nodes are literals, both signs of every variable. `a ∨ b` is `-a => b` and `-b => a`.
+    pub fn implication_graph(&self) -> Graph<i64> {
+        let mut graph = Graph::new();
+        for x in 1..=self.variables as i64 {
+            graph.add_node(&x);
+            graph.add_node(&-x);
+        }
+        for &(a, b) in &self.clauses {
+            graph.add_edge(-a, b);
+            graph.add_edge(-b, a);
+        }
+        graph
+    }
+
+    // Unsatisfiable exactly when some x and -x share a component. Otherwise each variable
+    // takes the value whose literal's component comes later in topological order: Tarjan
+    // numbers components sinks first, so that's the lower number. Nothing later can imply
+    // anything earlier, so no true literal ever implies a false one.
+    pub fn solve(&self) -> Result<Assignment, Conflict> {
+        let graph = self.implication_graph();
+        let components = tarjan(&graph);
+        let mut values = Vec::with_capacity(self.variables);
+        for x in 1..=self.variables as i64 {
+            let (positive, negative) = (components.of[&x], components.of[&-x]);
+            if positive == negative {
+                return Err(conflict(&graph, &components, x));
+            }
+            values.push(positive < negative);
+        }
+        let assignment = Assignment { values };
+        debug_assert!(assignment.satisfies(self));
+        Ok(assignment)
+    }
+}
+
+// x and -x are in one component, so there are paths both ways
+fn conflict(graph: &Graph<i64>, components: &Components<i64>, x: i64) -> Conflict {
+    debug_assert_eq!(components.of[&x], components.of[&-x]);
+    let there = bfs(graph, Some(x), Forward).path_to(&-x).unwrap();
+    let back = bfs(graph, Some(-x), Forward).path_to(&x).unwrap();
+    let mut cycle = there;
+    cycle.extend_from_slice(&back[1..]);
+    Conflict { variable: x, cycle }
+}
+
+// cargo run two-sat [--input FILE] [--lenient]
+pub fn main(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
+    let sat = match &args.input {
+        Some(path) => {
+            let (sat, warnings) = TwoSat::load_from_file(path, args.load_mode())?;
+            report(&warnings);
+            sat
+        }
+        None => TwoSat::parse(SCHEDULE)?,
+    };
+    let answer = match sat.solve() {
+        Ok(assignment) => Satisfiability {
+            satisfiable: true,
+            variables: sat.variables,
+            clauses: sat.clauses.len(),
+            assignment: assignment.literals(),
+            conflict: None,
+        },
+        Err(conflict) => Satisfiability {
+            satisfiable: false,
+            variables: sat.variables,
+            clauses: sat.clauses.len(),
+            assignment: Vec::new(),
+            conflict: Some(conflict),
+        },
+    };
+    Ok(Box::new(answer))
+}
+
+#[derive(Debug, Serialize)]
+pub struct Satisfiability {
+    pub satisfiable: bool,
+    pub variables: usize,
+    pub clauses: usize,
+    // One literal per variable, negative for false; empty if unsatisfiable
+    pub assignment: Vec<i64>,
+    pub conflict: Option<Conflict>,
+}
+
+impl fmt::Display for Satisfiability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} variables, {} clauses: ",
+            self.variables, self.clauses
+        )?;
+        match &self.conflict {
+            Some(conflict) => write!(f, "unsatisfiable, {}", conflict),
+            None if self.assignment.len() <= 20 => {
+                write!(f, "satisfiable by {:?}", self.assignment)
+            }
+            None => write!(
+                f,
+                "satisfiable by {:?} and {} more",
+                &self.assignment[..20],
+                self.assignment.len() - 20
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn the_schedule() {
+        let sat = TwoSat::parse(SCHEDULE).unwrap();
+        assert_eq!(sat.variables, 4);
+        assert_eq!(sat.clauses.len(), 5);
+        let assignment = sat.solve().unwrap();
+        assert!(assignment.satisfies(&sat));
+        // The only way: talk 2 in the morning, everything else in the afternoon
+        assert_eq!(assignment.literals(), vec![-1, 2, -3, -4]);
+
+        let answer = main(&crate::cli::Args::default()).unwrap();
+        assert_eq!(
+            answer.to_string(),
+            "4 variables, 5 clauses: satisfiable by [-1, 2, -3, -4]"
+        );
+    }
+
+    #[test]
+    fn conflicts_come_with_a_cycle() {
+        // 1 => 2 => -1 and -1 => 1
+        let sat = TwoSat::parse("2\n-1 2\n-2 -1\n1 1\n").unwrap();
+        let conflict = sat.solve().unwrap_err();
+        assert_eq!(conflict.variable, 1);
+        assert_eq!(conflict.cycle, vec![1, 2, -1, 1]);
+        assert_eq!(
+            conflict.to_string(),
+            "1 and -1 imply each other: 1 => 2 => -1 => 1"
+        );
+        // Every step of the cycle is an implication of some clause
+        let graph = sat.implication_graph();
+        for step in conflict.cycle.windows(2) {
+            assert!(graph.has_edge(&step[0], &step[1]));
+        }
+    }
+
+    #[test]
+    fn bad_clause_files() {
+        let error = |text: &str| TwoSat::parse(text).unwrap_err().to_string();
+        assert_eq!(error(""), "<clauses>: no variable count");
+        assert_eq!(
+            error("two\n1 2\n"),
+            "<clauses>:1:1: expected a number, found \"two\""
+        );
+        assert_eq!(
+            error("2\n1 2\n\n1 0\n"),
+            "<clauses>:4:3: expected a literal of variables 1 to 2, found \"0\""
+        );
+        assert_eq!(
+            error("2\n1 -3\n"),
+            "<clauses>:2:3: expected a literal of variables 1 to 2, found \"-3\""
+        );
+        assert_eq!(
+            error("2\n1 2 -1\n"),
+            "<clauses>:2:5: expected the end of the line, found \"-1\""
+        );
+        assert_eq!(
+            error("2\n  1\n"),
+            "<clauses>:2:3: expected two literals, found \"1\""
+        );
+        assert_eq!(
+            error("-2\n1 2\n"),
+            "<clauses>:1:1: expected the variable count, found \"-2\""
+        );
+        // Blank lines are fine, and so are variables in no clause
+        let sat = TwoSat::parse("\n3\n\n1 -2\n").unwrap();
+        assert_eq!(sat.solve().unwrap().literals().len(), 3);
+    }
+
+    #[test]
+    fn lenient_leaves_bad_clauses_out() {
+        let text = io::Cursor::new("3\n1 2\n1 x\n4 -1\n-3\n2 3\n");
+        let file = Path::new("clauses.txt");
+        let records = Records::new(file, text, Mode::Lenient, clause_line);
+        let (sat, warnings) = TwoSat::read(file, records, Mode::Lenient).unwrap();
+        assert_eq!(sat.clauses, vec![(1, 2), (2, 3)]);
+        let lines: Vec<(usize, usize)> = warnings.iter().map(|e| (e.line, e.column)).collect();
+        assert_eq!(lines, vec![(3, 3), (4, 1), (5, 1)]);
+    }
+
+    #[test]
+    fn agrees_with_brute_force() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let (mut satisfiable, mut unsatisfiable) = (0, 0);
+        for _ in 0..300 {
+            let n = rng.gen_range(1..8usize);
+            let m = rng.gen_range(0..3 * n);
+            let mut literal = || {
+                let x = rng.gen_range(1..=n as i64);
+                if rng.gen() {
+                    x
+                } else {
+                    -x
+                }
+            };
+            let clauses = (0..m).map(|_| (literal(), literal())).collect();
+            let sat = TwoSat {
+                variables: n,
+                clauses,
+            };
+            let exists = (0..1u32 << n).any(|bits| {
+                let values = (0..n).map(|i| bits >> i & 1 == 1).collect();
+                Assignment { values }.satisfies(&sat)
+            });
+            match sat.solve() {
+                Ok(assignment) => {
+                    assert!(assignment.satisfies(&sat), "{:?}", sat);
+                    satisfiable += 1;
+                }
+                Err(conflict) => {
+                    assert!(!exists, "{:?}", sat);
+                    let graph = sat.implication_graph();
+                    assert_eq!(conflict.cycle.first(), Some(&conflict.variable));
+                    assert_eq!(conflict.cycle.last(), Some(&conflict.variable));
+                    assert!(conflict.cycle.contains(&-conflict.variable));
+                    for step in conflict.cycle.windows(2) {
+                        assert!(graph.has_edge(&step[0], &step[1]));
+                    }
+                    unsatisfiable += 1;
+                }
+            }
+        }
+        // Both kinds actually came up
+        assert!(satisfiable > 30 && unsatisfiable > 30);
+    }
+}
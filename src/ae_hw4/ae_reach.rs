@@ -0,0 +1,142 @@
+crate::entry_point!("reach", main, {
+    category: Hw4,
+    description: "Reachability index over the SCC graph: build it once, then answer \"can u reach v?\" without searching",
+    datasets: ["SCC.txt"],
+    params: [
+        "--input FILE" => "directed edges, one `from to` pair per line (default data/SCC.txt)",
+        "--lenient" => "skip lines that don't parse, with a warning each",
+        "--seed N" => "seed the random queries asked when no pairs are given",
+        "bitset|intervals" => "how to label the condensation (default: bitset if it has at most 8192 components)",
+        "FROM TO..." => "pairs of nodes to ask about (default: 100000 random pairs)",
+    ],
+});
+
+use crate::graph::csr::CsrGraph;
+use crate::graph::parse::report;
+use crate::graph::reachability::{Reachability, Strategy};
+use crate::graph::Adjacency;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+use std::fmt;
+use std::time::Instant;
+
+const RANDOM_QUERIES: usize = 100_000;
+
+// cargo run reach [bitset|intervals] [FROM TO...]
+pub fn main(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
+    let (graph, warnings): (CsrGraph<usize>, _) = CsrGraph::load_from_directed_edges_file(
+        args.input_or_dataset("SCC.txt")?,
+        args.load_mode(),
+    )?;
+    report(&warnings);
+    if graph.node_count() == 0 {
+        return Err("graph is empty".into());
+    }
+    let mut words = args.positional.as_slice();
+    let strategy = match words.first().map(|w| w.parse::<Strategy>()) {
+        Some(Ok(strategy)) => {
+            words = &words[1..];
+            Some(strategy)
+        }
+        _ => None,
+    };
+    if words.len() % 2 == 1 {
+        return Err("nodes to ask about come in FROM TO pairs".into());
+    }
+    let mut pairs = Vec::new();
+    for pair in words.chunks(2) {
+        let index = |word: &String| -> Result<u32, crate::cli::Error> {
+            let id: usize = word
+                .parse()
+                .map_err(|_| format!("bad node id {:?}", word))?;
+            Ok(graph
+                .index_of(&id)
+                .ok_or_else(|| format!("node {} is not in the graph", id))?)
+        };
+        pairs.push((index(&pair[0])?, index(&pair[1])?));
+    }
+
+    let index = match strategy {
+        Some(strategy) => Reachability::with_strategy(&graph, strategy)?,
+        None => Reachability::new(&graph),
+    };
+    let random = pairs.is_empty();
+    if random {
+        let mut rng = match args.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let n = graph.node_count() as u32;
+        pairs = (0..RANDOM_QUERIES)
+            .map(|_| (rng.gen_range(0..n), rng.gen_range(0..n)))
+            .collect();
+    }
+    let start = Instant::now();
+    let answers: Vec<bool> = pairs.iter().map(|(u, v)| index.reaches(u, v)).collect();
+    let query_time = start.elapsed();
+
+    Ok(Box::new(Reach {
+        strategy: index.strategy().to_string(),
+        components: index.components.count(),
+        dag_edges: index.dag_edges,
+        index_bytes: index.size_bytes(),
+        build_ms: index.build_time.as_secs_f64() * 1e3,
+        query_ns: query_time.as_secs_f64() * 1e9 / pairs.len() as f64,
+        queries: pairs.len(),
+        reachable: answers.iter().filter(|&&a| a).count(),
+        // Random ones are only counted
+        answers: if random {
+            Vec::new()
+        } else {
+            pairs
+                .iter()
+                .zip(&answers)
+                .map(|(&(u, v), &reaches)| (*graph.id(u), *graph.id(v), reaches))
+                .collect()
+        },
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct Reach {
+    pub strategy: String,
+    pub components: usize,
+    pub dag_edges: usize,
+    pub index_bytes: usize,
+    pub build_ms: f64,
+    // Average per query
+    pub query_ns: f64,
+    pub queries: usize,
+    pub reachable: usize,
+    // (from, to, reaches) for the pairs that were asked about
+    pub answers: Vec<(usize, usize, bool)>,
+}
+
+impl fmt::Display for Reach {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} index over {} components and {} DAG edges: {:.1} MiB, built in {:.0} ms",
+            self.strategy,
+            self.components,
+            self.dag_edges,
+            self.index_bytes as f64 / (1 << 20) as f64,
+            self.build_ms
+        )?;
+        write!(
+            f,
+            "{} of {} queries reachable, {:.0} ns each",
+            self.reachable, self.queries, self.query_ns
+        )?;
+        for (from, to, reaches) in &self.answers {
+            write!(
+                f,
+                "\n{} {} {}",
+                from,
+                if *reaches { "reaches" } else { "doesn't reach" },
+                to
+            )?;
+        }
+        Ok(())
+    }
+}
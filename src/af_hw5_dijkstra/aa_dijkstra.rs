@@ -1,9 +1,22 @@
 #![allow(dead_code)]
 
-crate::entry_point!("dijkstra", main);
-use petgraph::{algo::dijkstra, prelude::UnGraph, visit::GraphBase, Graph};
+crate::entry_point!("dijkstra", main, {
+    category: Hw5,
+    description: "Shortest path distances from node 1 with Dijkstra (via petgraph)",
+    datasets: ["dijkstraData.txt"],
+    params: [
+        "--input FILE" => "weighted adjacency lists `v w,len ...` (default data/dijkstraData.txt)",
+        "--lenient" => "skip lines that don't parse, with a warning each",
+    ],
+});
+use crate::graph::formats::{self, AdjacencyLine, Number};
+use crate::graph::parse::{report, LoadError, Mode, ParseError, Records};
+use petgraph::{algo::dijkstra, prelude::UnGraph, Graph};
 // fxhash is my HashMap now
 use fxhash::FxHashMap as HashMap;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
 
 // Returns empty directed graph with node and edge weights of type i32
 pub fn sample_graph() -> Graph<i32, i32> {
@@ -17,35 +30,48 @@ pub fn sample_undirected() -> UnGraph<i32, i32> {
 type G = Graph<i32, i32>;
 
 // Read a directed graph from a file which contains a list of nodes and edges in a form (node,
-// weight). All the nodes have weight 1.
+// weight). Each node's weight is its number in the file.
 // Sample of the file:
 // 1	80,982	163,8164	170,2620	145,648	200,8021	173,2069	92,647	26,4122	140,546	11,1913	160,6461	27,7905	40,9047	150,2183	61,9146	159,7420	198,1724	114,508	104,6647	30,4612	99,2367	138,7896	169,8700	49,2437	125,2909	117,2597	55,6399
 // 2	42,1689	127,9365	5,8026	170,9342	131,7005	172,1438	34,315	30,2455	26,2328	6,8847	11,1873	17,5409	157,8643	159,1397	142,7731	182,7908	93,8177
 // 3	57,1239	101,3381	43,7313	41,7212	91,2483	31,3031	167,3877	106,6521	76,7729	122,9640	144,285	44,2165	6,9006	177,7097	119,7711
 // We parse it into a petgraph Graph to learn how to use petgraph.
-pub fn read_graph_from_file(filename: &str) -> Graph<i32, i32> {
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
+// A bad line is an error, or with `Mode::Lenient` a warning in the second half of the result.
+pub fn read_graph_from_file(
+    filename: impl AsRef<std::path::Path>,
+    mode: Mode,
+) -> Result<(Graph<i32, i32>, Vec<ParseError>), LoadError> {
+    read_graph(Records::open(
+        filename,
+        mode,
+        formats::weighted_adjacency_line,
+    )?)
+}
+
+// The same from lines that come from anywhere
+pub fn read_graph(
+    records: Records<AdjacencyLine<Number, i32>>,
+) -> Result<(Graph<i32, i32>, Vec<ParseError>), LoadError> {
     // First read the lines into an ordered adjacency vector, we'll be using iterator over it to
     // create the graph with add_node method, preserving the order of nodes.
 
     // Read line by line into a hashmap where key is the node index, which is the leftmost value,
     // and the value is a vector of tuples (node_index, weight).
+    let (lines, warnings) = records.read_all()?;
     let mut adjacency_list: HashMap<usize, Vec<(usize, i32)>> = HashMap::default();
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
-    for line in reader.lines() {
-        let line = line.unwrap();
-        let mut iter = line.split_whitespace();
-        let node_index = iter.next().unwrap().parse::<usize>().unwrap();
-        let mut edges: Vec<(usize, i32)> = Vec::new();
-        for edge in iter {
-            let mut edge_iter = edge.split(",");
-            let target_index = edge_iter.next().unwrap().parse::<usize>().unwrap();
-            let weight = edge_iter.next().unwrap().parse::<i32>().unwrap();
-            edges.push((target_index - 1, weight));
-        }
-        adjacency_list.insert(node_index - 1, edges);
+    let mut targets = Vec::new();
+    for (node, edges) in lines {
+        // 1-indexed in the file, 0-indexed from here on
+        let edges: Vec<(usize, i32)> = edges
+            .into_iter()
+            .map(|(target, weight)| (target.index(), weight))
+            .collect();
+        targets.extend(edges.iter().map(|&(target, _)| target));
+        adjacency_list.insert(node.index(), edges);
+    }
+    // Nodes that only show up as targets (say their own line was skipped) have no edges out
+    for target in targets {
+        adjacency_list.entry(target).or_default();
     }
 
     // Reenumerate the adjacency list to make sure that the node indices are in the range 0..n-1
@@ -81,9 +107,10 @@ pub fn read_graph_from_file(filename: &str) -> Graph<i32, i32> {
     // Now we can create the graph
     let mut g = Graph::new();
 
-    // Add nodes
-    for i in 0..adjacency_list.len() {
-        g.add_node(i as i32); // weight = node id just for easy debug
+    // Add nodes, with the 1-indexed label from the file as the weight: once a lenient load has
+    // skipped a line, position and label no longer match
+    for key in &keys {
+        g.add_node(*key as i32 + 1);
     }
 
     //Add edges
@@ -97,84 +124,65 @@ pub fn read_graph_from_file(filename: &str) -> Graph<i32, i32> {
         }
     }
 
-    g
+    Ok((g, warnings))
 }
 
-fn get_distance(
-    s: usize,
-    t: usize,
+// Distances from the node labelled `from` to each of `targets`, by their labels in the file
+fn distances_from(
     graph: &G,
-    distances: &HashMap<<G as GraphBase>::NodeId, HashMap<<G as GraphBase>::NodeId, i32>>,
-) -> i32 {
-    let from = graph.node_indices().nth(s).unwrap();
-    let to = graph.node_indices().nth(t).unwrap();
-    let distances = distances.get(&from).unwrap();
-    *distances.get(&to).unwrap()
+    from: usize,
+    targets: &[usize],
+) -> Result<BTreeMap<usize, i32>, crate::cli::Error> {
+    let index = |label: usize| {
+        graph
+            .node_indices()
+            .find(|&i| graph[i] == label as i32)
+            .ok_or_else(|| format!("node {} is not in the graph", label))
+    };
+    let reached = dijkstra(graph, index(from)?, None, |e| *e.weight());
+    let mut distances = BTreeMap::new();
+    for &t in targets {
+        let distance = reached
+            .get(&index(t)?)
+            .ok_or_else(|| format!("node {} can't be reached from {}", t, from))?;
+        distances.insert(t, *distance);
+    }
+    Ok(distances)
 }
 
-pub fn main() {
-    // Read dijkstraData.txt into graph in memory
-    let graph = read_graph_from_file("data/dijkstraData.txt");
-    // Run Dijkstra's algorithm on the graph
-    let mut distances: HashMap<<G as GraphBase>::NodeId, HashMap<<G as GraphBase>::NodeId, i32>> =
-        HashMap::default();
-    for i in 0..graph.node_count() {
-        let node_index = graph.node_indices().nth(i).unwrap();
-        // let mut inner_distances: HashMap<<G as GraphBase>::NodeId, i32> = HashMap::default();
-        /*
-        for j in 0..graph.node_count() {
-            let target_index = graph.node_indices().nth(j).unwrap();
-            let distance = dijkstra(&graph, node_index, Some(target_index), |e| {
-                *e.weight() as i32
-            });
-            // dbg!(&distance);
-            // dbg!((&i, &j, &node_index, &target_index, graph.node_count()));
-            if let Some(distance) = distance.get(&target_index) {
-                inner_distances.insert(target_index, *distance);
+pub fn main(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
+    // Read dijkstraData.txt (or whatever --input points at) into graph in memory
+    let (graph, warnings) =
+        read_graph_from_file(args.input_or_dataset("dijkstraData.txt")?, args.load_mode())?;
+    report(&warnings);
+    // The nodes the assignment asks about, 1-indexed like in the file.
+    let targets = [7, 37, 59, 82, 99, 115, 133, 165, 188, 197];
+    let distances = distances_from(&graph, 1, &targets)?;
+    Ok(Box::new(Distances { from: 1, distances }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct Distances {
+    pub from: usize,
+    pub distances: BTreeMap<usize, i32>,
+}
+
+impl fmt::Display for Distances {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (to, distance)) in self.distances.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
             }
+            write!(f, "{}-{}\n{}", self.from, to, distance)?;
         }
-        */
-        // Get 7th node_index
-        let target_index = graph.node_indices().nth(6).unwrap();
-        let inner_distances = dijkstra(&graph, node_index, Some(target_index), |e| {
-            *e.weight() as i32
-        })
-        .iter()
-        .fold(HashMap::default(), |mut acc, (k, v)| {
-            acc.insert(*k, *v);
-            acc
-        });
-        distances.insert(node_index, inner_distances);
+        Ok(())
     }
-    dbg!(&distances
-        .get(&graph.node_indices().nth(0).unwrap())
-        .unwrap());
-    // dbg!(&distances);
-    println!("1-7");
-    println!("{}", get_distance(0, 6, &graph, &distances));
-    println!("1-37");
-    println!("{}", get_distance(0, 36, &graph, &distances));
-    println!("1-59");
-    println!("{}", get_distance(0, 58, &graph, &distances));
-    println!("1-82");
-    println!("{}", get_distance(0, 81, &graph, &distances));
-    println!("1-99");
-    println!("{}", get_distance(0, 98, &graph, &distances));
-    println!("1-115");
-    println!("{}", get_distance(0, 114, &graph, &distances));
-    println!("1-133");
-    println!("{}", get_distance(0, 132, &graph, &distances));
-    println!("1-165");
-    println!("{}", get_distance(0, 164, &graph, &distances));
-    println!("1-188");
-    println!("{}", get_distance(0, 187, &graph, &distances));
-    println!("1-197");
-    println!("{}", get_distance(0, 196, &graph, &distances));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use petgraph::graph::NodeIndex;
     #[test]
     fn test_simple_well_connected_5() {
         let mut g = Graph::new();
@@ -200,41 +208,35 @@ mod tests {
     }
 
     #[test]
-    fn test_hourglasses() {
-        let mut g = Graph::new();
-        for _ in 0..5 {
-            g.add_node(0);
-        }
-        g.add_edge(
-            g.node_indices().nth(0).unwrap(),
-            g.node_indices().nth(1).unwrap(),
-            1,
-        );
-        g.add_edge(
-            g.node_indices().nth(1).unwrap(),
-            g.node_indices().nth(2).unwrap(),
-            5,
-        );
-        g.add_edge(
-            g.node_indices().nth(2).unwrap(),
-            g.node_indices().nth(0).unwrap(),
-            7,
-        );
-        g.add_edge(
-            g.node_indices().nth(3).unwrap(),
-            g.node_indices().nth(2).unwrap(),
-            3,
-        );
-        g.add_edge(
-            g.node_indices().nth(3).unwrap(),
-            g.node_indices().nth(4).unwrap(),
-            2,
-        );
-        g.add_edge(
-            g.node_indices().nth(4).unwrap(),
-            g.node_indices().nth(2).unwrap(),
-            1,
+    fn test_targets_keep_their_labels() {
+        // Line 2 is broken and nothing else mentions node 2, so it's gone; 3 and 4 must not
+        // take its place
+        let text = "1\t3,10\t4,20\n2\t3;1\n3\t4,5\n4\t1,1\n";
+        let lines = |mode| {
+            let file = std::path::Path::new("x");
+            Records::new(
+                file,
+                text.as_bytes(),
+                mode,
+                formats::weighted_adjacency_line,
+            )
+        };
+        let (graph, warnings) = read_graph(lines(Mode::Lenient)).unwrap();
+        let strict = read_graph(lines(Mode::Strict));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!((warnings[0].line, warnings[0].token.as_str()), (2, "3;1"));
+        assert!(strict.is_err());
+        assert_eq!(graph.node_count(), 3);
+        let distances = distances_from(&graph, 1, &[3, 4]).unwrap();
+        assert_eq!(
+            distances.into_iter().collect::<Vec<_>>(),
+            vec![(3, 10), (4, 15)]
         );
+        assert!(distances_from(&graph, 1, &[2]).is_err());
+    }
+
+    #[test]
+    fn test_hourglasses() {
         // This graph looks like this:
         // 0 -> 1
         // ^    |
@@ -243,9 +245,16 @@ mod tests {
         //      ^   \
         //      |    \
         //      4<----3
-        let dj1 = dijkstra(&g, g.node_indices().nth(0).unwrap(), None, |e| *e.weight());
+        let g: Graph<i32, i32> = crate::graph! {
+            0 -> 1 [w = 1]; 1 -> 2 [w = 5]; 2 -> 0 [w = 7]; 3 -> 2 [w = 3], 4 [w = 2]; 4 -> 2 [w = 1]
+        };
+        // Nodes are indexed in the order they come up, which here is their id
+        let node = NodeIndex::new;
+        let dj1 = dijkstra(&g, node(0), None, |e| *e.weight());
         assert_eq!(dj1.keys().len(), 3);
-        let dj3 = dijkstra(&g, g.node_indices().nth(3).unwrap(), None, |e| *e.weight());
+        let dj3 = dijkstra(&g, node(3), None, |e| *e.weight());
         assert_eq!(dj3.keys().len(), 5);
+        assert_eq!(dj3[&node(2)], 3);
+        assert_eq!(dj3[&node(0)], 10);
     }
 }
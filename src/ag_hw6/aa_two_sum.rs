@@ -1,14 +1,22 @@
 // fxhash hashset
 use fxhash::FxHashSet as HashSet;
-crate::entry_point!("2sum_", main);
+use serde::Serialize;
+use std::fmt;
+crate::entry_point!("2sum_", main, {
+    category: Hw6,
+    description: "Count targets in [-10000, 10000] that are a sum of two distinct input numbers",
+    datasets: ["algo1-programming_prob-2sum.txt"],
+    params: ["--input FILE" => "integers, one per line (default data/algo1-programming_prob-2sum.txt)"],
+});
 
-pub fn main() {
+pub fn main(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
     let mut distinct = 0;
-    let hashset = read_hashset_from_file();
+    let hashset =
+        read_hashset_from_file(args.input_or_dataset("algo1-programming_prob-2sum.txt")?)?;
     for i in -10000..=10000 {
         // Diagnostics every 100 iterations
         if i % 500 == 0 {
-            println!("{}", i);
+            eprintln!("{}", i);
         }
         let mut count = 0;
         for x in hashset.iter() {
@@ -21,7 +29,18 @@ pub fn main() {
             distinct += 1;
         }
     }
-    println!("{}", distinct);
+    Ok(Box::new(TwoSum { targets: distinct }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct TwoSum {
+    pub targets: usize,
+}
+
+impl fmt::Display for TwoSum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.targets)
+    }
 }
 
 /*
@@ -30,16 +49,18 @@ pub fn main() {
 -21123414637
 56619844751
 */
-pub fn read_hashset_from_file() -> HashSet<i64> {
+pub fn read_hashset_from_file(
+    path: impl AsRef<std::path::Path>,
+) -> Result<HashSet<i64>, crate::cli::Error> {
     use std::fs::File;
     use std::io::{BufRead, BufReader};
-    let file = File::open("./data/algo1-programming_prob-2sum.txt").unwrap();
+    let file = File::open(path)?;
     let reader = BufReader::new(file);
     let mut hashset = HashSet::default();
     for line in reader.lines() {
-        let line = line.unwrap();
-        let num: i64 = line.parse().unwrap();
+        let line = line?;
+        let num: i64 = line.trim().parse()?;
         hashset.insert(num);
     }
-    hashset
+    Ok(hashset)
 }
@@ -1,6 +1,13 @@
-crate::entry_point!("median_", main);
+crate::entry_point!("median_", main, {
+    category: Hw6,
+    description: "Sum of running medians modulo 10000, with two heaps",
+    datasets: ["Median.txt"],
+    params: ["--input FILE" => "integers, one per line (default data/Median.txt)"],
+});
 // Binaryheap
+use serde::Serialize;
 use std::collections::BinaryHeap;
+use std::fmt;
 
 // Min heap stores i64 with inverted Ord
 #[derive(Eq, PartialEq, Debug)]
@@ -16,7 +23,7 @@ impl Ord for MinInt {
     }
 }
 
-pub fn main() {
+pub fn main(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
     // Create Min-heap and Max-heap
     let mut high: BinaryHeap<MinInt> = BinaryHeap::new();
     let mut low: BinaryHeap<i64> = BinaryHeap::new();
@@ -24,14 +31,14 @@ pub fn main() {
     // Stream ints from file
     use std::fs::File;
     use std::io::{BufRead, BufReader};
-    let file = File::open("./data/Median.txt").unwrap();
+    let file = File::open(args.input_or_dataset("Median.txt")?)?;
     let reader = BufReader::new(file);
     let mut i = 0;
     for line in reader.lines() {
         i += 1;
         // Diagnostics every 100 iterations
         if i % 100 == 0 {
-            println!(
+            eprintln!(
                 "{:?}@{} {:?}@{}",
                 &low.peek(),
                 &low.len(),
@@ -39,8 +46,8 @@ pub fn main() {
                 &high.len()
             )
         }
-        let line = line.unwrap();
-        let num: i64 = line.parse().unwrap();
+        let line = line?;
+        let num: i64 = line.trim().parse()?;
         let low_len = low.len();
         let high_len = high.len();
         // When we insert a new number into low, we need to move the max of low into high
@@ -71,5 +78,18 @@ pub fn main() {
     }
     // Return sum of medians mod 10000
     let sum: i64 = medians_over_time.iter().sum();
-    println!("{}", sum % 10000);
+    Ok(Box::new(MedianSum {
+        sum_mod_10000: sum % 10000,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct MedianSum {
+    pub sum_mod_10000: i64,
+}
+
+impl fmt::Display for MedianSum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.sum_mod_10000)
+    }
 }
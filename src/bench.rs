@@ -0,0 +1,201 @@
+use crate::cli;
+use crate::entry::EntryPoint;
+use crate::memory;
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// `bench`'s own flags; whatever it doesn't recognise goes to the entry point.
+#[derive(Debug, PartialEq)]
+pub struct BenchOptions {
+    pub runs: usize,
+    pub warmup: usize,
+    // Where to write the report as JSON, `-` for stdout
+    pub json: Option<PathBuf>,
+    pub entry_args: Vec<String>,
+}
+
+impl BenchOptions {
+    pub fn parse<I: IntoIterator<Item = String>>(argv: I) -> Result<Self, cli::ArgsError> {
+        let mut options = BenchOptions {
+            runs: 10,
+            warmup: 1,
+            json: None,
+            entry_args: Vec::new(),
+        };
+        let mut argv = argv.into_iter();
+        while let Some(arg) = argv.next() {
+            let mut value = |flag: &str| {
+                argv.next()
+                    .ok_or_else(|| cli::ArgsError::MissingValue(flag.to_string()))
+            };
+            let number = |flag: &str, value: String| {
+                value.parse().map_err(|_| cli::ArgsError::InvalidValue {
+                    flag: flag.to_string(),
+                    value,
+                })
+            };
+            match arg.as_str() {
+                "--runs" => options.runs = number(&arg, value(&arg)?)?,
+                "--warmup" => options.warmup = number(&arg, value(&arg)?)?,
+                "--json" => options.json = Some(PathBuf::from(value(&arg)?)),
+                _ => options.entry_args.push(arg),
+            }
+        }
+        if options.runs == 0 {
+            return Err(cli::ArgsError::InvalidValue {
+                flag: "--runs".to_string(),
+                value: "0".to_string(),
+            });
+        }
+        Ok(options)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchReport {
+    pub entry: String,
+    pub args: Vec<String>,
+    pub warmup: usize,
+    pub runs_ms: Vec<f64>,
+    pub min_ms: f64,
+    pub median_ms: f64,
+    pub mean_ms: f64,
+    pub stddev_ms: f64,
+    // Most bytes the entry point had allocated at once, over all runs
+    pub peak_bytes: usize,
+}
+
+impl BenchReport {
+    pub fn from_runs(entry: &str, options: &BenchOptions, runs_ms: Vec<f64>, peak: usize) -> Self {
+        let mut sorted = runs_ms.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let n = sorted.len();
+        let median_ms = if n % 2 == 1 {
+            sorted[n / 2]
+        } else {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        };
+        let mean_ms = sorted.iter().sum::<f64>() / n as f64;
+        // Sample standard deviation; a single run has none.
+        let stddev_ms = if n > 1 {
+            let var = sorted.iter().map(|t| (t - mean_ms).powi(2)).sum::<f64>() / (n - 1) as f64;
+            var.sqrt()
+        } else {
+            0.0
+        };
+        BenchReport {
+            entry: entry.to_string(),
+            args: options.entry_args.clone(),
+            warmup: options.warmup,
+            min_ms: sorted[0],
+            median_ms,
+            mean_ms,
+            stddev_ms,
+            peak_bytes: peak,
+            runs_ms,
+        }
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {} ({} runs, {} warmup)",
+            self.entry,
+            self.args.join(" "),
+            self.runs_ms.len(),
+            self.warmup
+        )?;
+        writeln!(f, "  min     {:>12.3} ms", self.min_ms)?;
+        writeln!(f, "  median  {:>12.3} ms", self.median_ms)?;
+        writeln!(f, "  mean    {:>12.3} ms", self.mean_ms)?;
+        writeln!(f, "  stddev  {:>12.3} ms", self.stddev_ms)?;
+        write!(
+            f,
+            "  peak    {:>12.3} MiB",
+            self.peak_bytes as f64 / (1024.0 * 1024.0)
+        )
+    }
+}
+
+pub fn bench(entry: &EntryPoint, options: &BenchOptions) -> Result<BenchReport, cli::Error> {
+    let args = cli::Args::parse(options.entry_args.clone())?;
+    for _ in 0..options.warmup {
+        (entry.run)(&args)?;
+    }
+    let mut runs_ms = Vec::with_capacity(options.runs);
+    let mut peak = 0;
+    for _ in 0..options.runs {
+        let baseline = memory::current();
+        memory::reset_peak();
+        let start = Instant::now();
+        let answer = (entry.run)(&args)?;
+        runs_ms.push(start.elapsed().as_secs_f64() * 1000.0);
+        peak = peak.max(memory::peak().saturating_sub(baseline));
+        drop(answer);
+    }
+    Ok(BenchReport::from_runs(entry.name, options, runs_ms, peak))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(xs: &[&str]) -> Vec<String> {
+        xs.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn options_split_from_entry_args() {
+        let options = BenchOptions::parse(argv(&[
+            "--runs",
+            "5",
+            "--input",
+            "data/kargerTriangle.txt",
+            "--warmup",
+            "0",
+            "--json",
+            "-",
+        ]))
+        .unwrap();
+        assert_eq!(
+            options,
+            BenchOptions {
+                runs: 5,
+                warmup: 0,
+                json: Some(PathBuf::from("-")),
+                entry_args: argv(&["--input", "data/kargerTriangle.txt"]),
+            }
+        );
+        assert!(BenchOptions::parse(argv(&["--runs", "0"])).is_err());
+        assert!(BenchOptions::parse(argv(&["--warmup"])).is_err());
+    }
+
+    #[test]
+    fn statistics() {
+        let options = BenchOptions::parse(argv(&[])).unwrap();
+        let report = BenchReport::from_runs("x", &options, vec![4.0, 1.0, 3.0, 2.0], 10);
+        assert_eq!(report.min_ms, 1.0);
+        assert_eq!(report.median_ms, 2.5);
+        assert_eq!(report.mean_ms, 2.5);
+        assert!((report.stddev_ms - 1.2910).abs() < 1e-4);
+        assert_eq!(report.runs_ms, vec![4.0, 1.0, 3.0, 2.0]);
+    }
+
+    #[test]
+    fn benches_an_entry_point() {
+        let entry = crate::ENTRY_POINTS
+            .iter()
+            .find(|e| e.name == "karatsuba")
+            .unwrap();
+        let options = BenchOptions::parse(argv(&["--runs", "3", "12", "34"])).unwrap();
+        let report = bench(entry, &options).unwrap();
+        assert_eq!(report.runs_ms.len(), 3);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["entry"], "karatsuba");
+        assert_eq!(json["args"], serde_json::json!(["12", "34"]));
+    }
+}
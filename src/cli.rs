@@ -0,0 +1,256 @@
+use crate::dataset;
+use crate::graph::parse::Mode;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+// Whatever an entry point fails with. Boxed so that `?` works on io, parse and our own errors
+// alike; Send + Sync so that it can travel between threads.
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Everything after the entry point name on the command line.
+///
+/// ```
+/// cargo run karger --input data/kargerTriangle.txt --seed 42 --iterations 100
+/// cargo run dijkstra --input hand-edited.txt --lenient
+/// cargo run karatsuba 1234 5678
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Args {
+    pub input: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub iterations: Option<usize>,
+    // Graph loaders skip lines they can't read instead of failing
+    pub lenient: bool,
+    pub positional: Vec<String>,
+}
+
+/// How `main` prints answers: `--format text` (the default) or `--format json`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format {:?}, expected text or json", s)),
+        }
+    }
+}
+
+/// Flags that apply to every command and entry point alike.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Globals {
+    pub format: Format,
+    // Where the course files are, instead of looking for them (see `dataset::data_root`)
+    pub data_root: Option<PathBuf>,
+}
+
+// Pulls the global --format and --data-root flags out of argv, wherever they are, so that
+// commands and entry points never see them.
+pub fn take_globals(argv: Vec<String>) -> Result<(Globals, Vec<String>), ArgsError> {
+    let mut globals = Globals::default();
+    let mut rest = Vec::with_capacity(argv.len());
+    let mut argv = argv.into_iter();
+    while let Some(arg) = argv.next() {
+        let (flag, inline) = match arg.find('=') {
+            Some(i) => (arg[..i].to_string(), Some(arg[i + 1..].to_string())),
+            None => (arg.clone(), None),
+        };
+        if flag != "--format" && flag != "--data-root" {
+            rest.push(arg);
+            continue;
+        }
+        let value = match inline {
+            Some(value) => value,
+            None => argv
+                .next()
+                .ok_or_else(|| ArgsError::MissingValue(flag.clone()))?,
+        };
+        if flag == "--format" {
+            globals.format = parse_value(&flag, value)?;
+        } else {
+            globals.data_root = Some(PathBuf::from(value));
+        }
+    }
+    Ok((globals, rest))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgsError {
+    UnknownFlag(String),
+    MissingValue(String),
+    InvalidValue { flag: String, value: String },
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgsError::UnknownFlag(flag) => write!(f, "unknown flag {}", flag),
+            ArgsError::MissingValue(flag) => write!(f, "flag {} needs a value", flag),
+            ArgsError::InvalidValue { flag, value } => {
+                write!(f, "invalid value {:?} for {}", value, flag)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArgsError {}
+
+impl Args {
+    pub fn parse<I: IntoIterator<Item = String>>(argv: I) -> Result<Self, ArgsError> {
+        let mut args = Args::default();
+        let mut argv = argv.into_iter();
+        while let Some(arg) = argv.next() {
+            if !arg.starts_with("--") {
+                args.positional.push(arg);
+                continue;
+            }
+            // Both `--seed 42` and `--seed=42` are fine.
+            let (flag, inline) = match arg.find('=') {
+                Some(i) => (arg[..i].to_string(), Some(arg[i + 1..].to_string())),
+                None => (arg.clone(), None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| argv.next())
+                    .ok_or_else(|| ArgsError::MissingValue(flag.clone()))
+            };
+            match flag.as_str() {
+                "--lenient" if inline.is_none() => args.lenient = true,
+                "--input" => args.input = Some(PathBuf::from(value()?)),
+                "--seed" => args.seed = Some(parse_value(&flag, value()?)?),
+                "--iterations" => args.iterations = Some(parse_value(&flag, value()?)?),
+                _ => return Err(ArgsError::UnknownFlag(flag)),
+            }
+        }
+        Ok(args)
+    }
+
+    // Input file given with --input, or the dataset the entry point was written for, wherever
+    // the data root turns out to be.
+    pub fn input_or_dataset(&self, name: &str) -> Result<PathBuf, dataset::DataRootError> {
+        match &self.input {
+            Some(input) => Ok(input.clone()),
+            None => dataset::resolve(name),
+        }
+    }
+
+    pub fn load_mode(&self) -> Mode {
+        if self.lenient {
+            Mode::Lenient
+        } else {
+            Mode::Strict
+        }
+    }
+
+    // Positional argument `i` parsed as `T`, if it's there at all.
+    pub fn arg<T: FromStr>(&self, i: usize) -> Result<Option<T>, ArgsError> {
+        match self.positional.get(i) {
+            Some(value) => parse_value(&format!("argument #{}", i + 1), value.clone()).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: String) -> Result<T, ArgsError> {
+    value.parse().map_err(|_| ArgsError::InvalidValue {
+        flag: flag.to_string(),
+        value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(xs: &[&str]) -> Vec<String> {
+        xs.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_flags_and_positionals() {
+        let args = Args::parse(argv(&[
+            "--input",
+            "data/kargerTriangle.txt",
+            "7",
+            "--seed=42",
+            "--iterations",
+            "100",
+            "--lenient",
+        ]))
+        .unwrap();
+        assert_eq!(args.input, Some(PathBuf::from("data/kargerTriangle.txt")));
+        assert_eq!(args.seed, Some(42));
+        assert_eq!(args.iterations, Some(100));
+        assert_eq!(args.load_mode(), Mode::Lenient);
+        assert_eq!(args.positional, vec!["7".to_string()]);
+        assert_eq!(args.arg::<u64>(0), Ok(Some(7)));
+        assert_eq!(args.arg::<u64>(1), Ok(None));
+    }
+
+    #[test]
+    fn input_overrides_dataset() {
+        let args = Args::parse(argv(&["--input", "elsewhere.txt"])).unwrap();
+        assert_eq!(
+            args.input_or_dataset("Median.txt").unwrap(),
+            PathBuf::from("elsewhere.txt")
+        );
+        let args = Args::parse(argv(&[])).unwrap();
+        if let Ok(path) = args.input_or_dataset("Median.txt") {
+            assert!(path.ends_with("Median.txt"));
+        }
+    }
+
+    #[test]
+    fn takes_globals_from_anywhere() {
+        let (globals, rest) =
+            take_globals(argv(&["karger", "--format", "json", "--seed", "1"])).unwrap();
+        assert_eq!(globals.format, Format::Json);
+        assert_eq!(rest, argv(&["karger", "--seed", "1"]));
+        let (globals, rest) = take_globals(argv(&[
+            "verify",
+            "--format=text",
+            "--data-root",
+            "/srv/data",
+        ]))
+        .unwrap();
+        assert_eq!(
+            globals,
+            Globals {
+                format: Format::Text,
+                data_root: Some(PathBuf::from("/srv/data")),
+            }
+        );
+        assert_eq!(rest, argv(&["verify"]));
+        assert!(take_globals(argv(&["list", "--format", "yaml"])).is_err());
+        assert!(take_globals(argv(&["list", "--data-root"])).is_err());
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert_eq!(
+            Args::parse(argv(&["--frobnicate"])),
+            Err(ArgsError::UnknownFlag("--frobnicate".to_string()))
+        );
+        assert_eq!(
+            Args::parse(argv(&["--seed"])),
+            Err(ArgsError::MissingValue("--seed".to_string()))
+        );
+        assert_eq!(
+            Args::parse(argv(&["--iterations", "many"])),
+            Err(ArgsError::InvalidValue {
+                flag: "--iterations".to_string(),
+                value: "many".to_string()
+            })
+        );
+    }
+}
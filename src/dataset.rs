@@ -0,0 +1,639 @@
+use crate::cli;
+use crate::entry::EntryPoint;
+use crate::util::project_root;
+use serde::Serialize;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// A file under data/ that something in this repo reads.
+pub struct Dataset {
+    pub name: &'static str,
+    // Expected size and CRC-32. The two files too big to commit only ever came as archives, and
+    // no copy of them was at hand to measure, so for those we only know they should exist; the
+    // note says so in `cargo run data`.
+    pub size: Option<u64>,
+    pub crc32: Option<u32>,
+    pub note: &'static str,
+}
+
+pub const CATALOGUE: &[Dataset] = &[
+    Dataset {
+        name: "IntegerArray.txt",
+        size: Some(688895),
+        crc32: Some(0x6ec5c184),
+        note: "100,000 integers, hw1",
+    },
+    Dataset {
+        name: "QuickSort.txt",
+        size: Some(58894),
+        crc32: Some(0xe680a7b7),
+        note: "10,000 integers, hw2",
+    },
+    Dataset {
+        name: "kargerMinCut.txt",
+        size: Some(18570),
+        crc32: Some(0xfad0df69),
+        note: "200-node undirected adjacency lists, hw3",
+    },
+    Dataset {
+        name: "kargerTriangle.txt",
+        size: Some(30),
+        crc32: Some(0xb5f14a89),
+        note: "karger test fixture",
+    },
+    Dataset {
+        name: "kargerEnvelopes.txt",
+        size: Some(128),
+        crc32: Some(0xe3980512),
+        note: "karger test fixture",
+    },
+    Dataset {
+        name: "SCC.txt",
+        size: None,
+        crc32: None,
+        note: "875,714-node directed edge list, hw4; not in git, ships as an archive, size and CRC never recorded",
+    },
+    Dataset {
+        name: "dijkstraData.txt",
+        size: Some(32237),
+        crc32: Some(0xf7a6acea),
+        note: "200-node weighted adjacency lists, hw5",
+    },
+    Dataset {
+        name: "algo1-programming_prob-2sum.txt",
+        size: None,
+        crc32: None,
+        note: "1,000,000 integers, hw6; not in git, ships as an archive, size and CRC never recorded",
+    },
+    Dataset {
+        name: "Median.txt",
+        size: Some(58894),
+        crc32: Some(0xcc571a40),
+        note: "10,000 integers, hw6",
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Dataset> {
+    CATALOGUE.iter().find(|d| d.name == name)
+}
+
+// Where the data root comes from when nobody says otherwise
+pub const DATA_ROOT_VAR: &str = "COMPETITIVE_DATA";
+
+// --data-root, set once by main before anything reads data
+static DATA_ROOT_FLAG: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_data_root(dir: PathBuf) {
+    let _ = DATA_ROOT_FLAG.set(dir);
+}
+
+/// One place `data_root` looked, and what was wrong with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tried {
+    pub how: &'static str,
+    pub path: PathBuf,
+    pub problem: &'static str,
+}
+
+/// No data directory anywhere we know to look.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataRootError {
+    pub tried: Vec<Tried>,
+}
+
+impl fmt::Display for DataRootError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "can't find the data directory, tried:")?;
+        for t in &self.tried {
+            writeln!(f, "  {} ({}): {}", t.path.display(), t.how, t.problem)?;
+        }
+        write!(
+            f,
+            "pass --data-root DIR or set {} to the directory with the course files",
+            DATA_ROOT_VAR
+        )
+    }
+}
+
+impl std::error::Error for DataRootError {}
+
+// In order: --data-root, $COMPETITIVE_DATA, data/ in the repo found from the executable or
+// the current directory, data/ next to the Cargo.toml this was built from. The first two are
+// the user being explicit, so if they're wrong that's an error rather than a reason to go
+// looking elsewhere.
+pub fn find_data_root(flag: Option<&Path>, var: Option<&Path>) -> Result<PathBuf, DataRootError> {
+    let mut tried = Vec::new();
+    let explicit = vec![("--data-root", flag), (DATA_ROOT_VAR, var)];
+    for (how, dir) in explicit {
+        if let Some(dir) = dir {
+            if dir.is_dir() {
+                return Ok(dir.to_owned());
+            }
+            tried.push(Tried {
+                how,
+                path: dir.to_owned(),
+                problem: "not a directory",
+            });
+            return Err(DataRootError { tried });
+        }
+    }
+    match project_root() {
+        Ok(root) => {
+            let dir = root.join("data");
+            if dir.is_dir() {
+                return Ok(dir);
+            }
+            tried.push(Tried {
+                how: "repo",
+                path: dir,
+                problem: "not a directory",
+            });
+        }
+        Err(e) => tried.extend(e.tried.into_iter().map(|path| Tried {
+            how: "looking for the repo",
+            path,
+            problem: "no .git",
+        })),
+    }
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data");
+    if dir.is_dir() {
+        return Ok(dir);
+    }
+    tried.push(Tried {
+        how: "build directory",
+        path: dir,
+        problem: "not a directory",
+    });
+    Err(DataRootError { tried })
+}
+
+pub fn data_root() -> Result<PathBuf, DataRootError> {
+    let var = std::env::var_os(DATA_ROOT_VAR).map(PathBuf::from);
+    find_data_root(DATA_ROOT_FLAG.get().map(PathBuf::as_path), var.as_deref())
+}
+
+// Path of a file in the data root; what every loader's default input goes through.
+pub fn resolve(name: &str) -> Result<PathBuf, DataRootError> {
+    data_root().map(|dir| dir.join(name))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Missing,
+    // Present, but not the file we know
+    WrongSize { expected: u64, actual: u64 },
+    WrongChecksum { expected: u32, actual: u32 },
+    // Present and nothing recorded to check it against
+    Unchecked,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Ok => write!(f, "ok"),
+            Status::Missing => write!(f, "MISSING"),
+            Status::WrongSize { expected, actual } => {
+                write!(f, "WRONG SIZE {} bytes, expected {}", actual, expected)
+            }
+            Status::WrongChecksum { expected, actual } => {
+                write!(
+                    f,
+                    "WRONG CHECKSUM {:08x}, expected {:08x}",
+                    actual, expected
+                )
+            }
+            Status::Unchecked => write!(f, "present (no checksum on record)"),
+        }
+    }
+}
+
+impl Dataset {
+    pub fn path_in(&self, dir: &Path) -> PathBuf {
+        dir.join(self.name)
+    }
+
+    pub fn status_in(&self, dir: &Path) -> io::Result<Status> {
+        let path = self.path_in(dir);
+        let actual = match fs::metadata(&path) {
+            Ok(meta) => meta.len(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Status::Missing),
+            Err(e) => return Err(e),
+        };
+        if let Some(expected) = self.size {
+            if expected != actual {
+                return Ok(Status::WrongSize { expected, actual });
+            }
+        }
+        match self.crc32 {
+            Some(expected) => {
+                let actual = crc32_file(&path)?;
+                if expected == actual {
+                    Ok(Status::Ok)
+                } else {
+                    Ok(Status::WrongChecksum { expected, actual })
+                }
+            }
+            None => Ok(Status::Unchecked),
+        }
+    }
+}
+
+// CRC-32 (the zlib/PNG one), table driven. Plenty fast for a few megabytes of course data.
+fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut c = i as u32;
+        for _ in 0..8 {
+            c = if c & 1 == 1 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+        }
+        *entry = c;
+    }
+    table
+}
+
+fn crc32_update(table: &[u32; 256], crc: u32, bytes: &[u8]) -> u32 {
+    bytes.iter().fold(crc, |crc, &b| {
+        table[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+pub fn crc32_file(path: &Path) -> io::Result<u32> {
+    let table = crc32_table();
+    let mut file = File::open(path)?;
+    let mut buf = vec![0u8; 1 << 16];
+    let mut crc = !0u32;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            return Ok(!crc);
+        }
+        crc = crc32_update(&table, crc, &buf[..n]);
+    }
+}
+
+// Plain ustar archives only: no compression, no GNU long names. That's what `tar cf` makes
+// for files with short names, which is all we need for data/.
+//
+// Only regular files whose name (without directories) is in the catalogue get extracted,
+// and they always land directly in `dir`, whatever path the archive had them under.
+pub fn extract_tar(archive: &Path, dir: &Path) -> io::Result<Vec<&'static str>> {
+    let mut file = File::open(archive)?;
+    let mut extracted = Vec::new();
+    let mut header = [0u8; 512];
+    loop {
+        if read_block(&mut file, &mut header)? == 0 || header.iter().all(|&b| b == 0) {
+            return Ok(extracted);
+        }
+        let invalid = |what: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", archive.display(), what),
+            )
+        };
+        let stored =
+            parse_octal(&header[148..156]).ok_or_else(|| invalid("bad header checksum field"))?;
+        // The checksum counts its own field as spaces.
+        let computed: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    b' ' as u64
+                } else {
+                    b as u64
+                }
+            })
+            .sum();
+        if stored != computed {
+            return Err(invalid("header checksum mismatch, not a tar file?"));
+        }
+        let size = parse_octal(&header[124..136]).ok_or_else(|| invalid("bad size field"))?;
+        let name = {
+            let field = |range: std::ops::Range<usize>| {
+                let raw = &header[range];
+                let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+                String::from_utf8_lossy(&raw[..end]).into_owned()
+            };
+            let (prefix, name) = (field(345..500), field(0..100));
+            if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                format!("{}/{}", prefix, name)
+            } else {
+                name
+            }
+        };
+        let regular = header[156] == b'0' || header[156] == 0;
+        let basename = Path::new(&name)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("");
+        let target = find(basename).filter(|_| regular);
+        // Contents are padded to whole blocks.
+        let padded = size.div_ceil(512) * 512;
+        match target {
+            Some(dataset) => {
+                let mut out = File::create(dataset.path_in(dir))?;
+                let copied = io::copy(&mut (&mut file).take(size), &mut out)?;
+                if copied != size {
+                    return Err(invalid("archive ends in the middle of a file"));
+                }
+                io::copy(&mut (&mut file).take(padded - size), &mut io::sink())?;
+                extracted.push(dataset.name);
+            }
+            None => {
+                io::copy(&mut (&mut file).take(padded), &mut io::sink())?;
+            }
+        }
+    }
+}
+
+fn read_block(file: &mut File, block: &mut [u8; 512]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < block.len() {
+        match file.read(&mut block[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let digits = field
+        .iter()
+        .skip_while(|&&b| b == b' ')
+        .take_while(|&&b| b != 0 && b != b' ');
+    let mut value = 0u64;
+    for &b in digits {
+        match b {
+            b'0'..=b'7' => value = value.checked_mul(8)?.checked_add((b - b'0') as u64)?,
+            _ => return None,
+        }
+    }
+    Some(value)
+}
+
+// Every .tar file directly in `dir`.
+pub fn archives_in(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut archives: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("tar"))
+        .collect();
+    archives.sort();
+    Ok(archives)
+}
+
+#[derive(Serialize)]
+pub struct Row {
+    pub name: &'static str,
+    pub status: String,
+    pub missing: bool,
+    pub note: &'static str,
+    pub used_by: Vec<&'static str>,
+}
+
+/// `cargo run data`: one row per catalogue entry, with the entry points that read it.
+#[derive(Serialize)]
+pub struct Report {
+    pub dir: PathBuf,
+    pub datasets: Vec<Row>,
+}
+
+pub fn report(dir: &Path, entry_points: &[EntryPoint]) -> io::Result<Report> {
+    let mut datasets = Vec::new();
+    for dataset in CATALOGUE {
+        let status = dataset.status_in(dir)?;
+        datasets.push(Row {
+            name: dataset.name,
+            missing: status == Status::Missing,
+            status: status.to_string(),
+            note: dataset.note,
+            used_by: entry_points
+                .iter()
+                .filter(|e| e.datasets.contains(&dataset.name))
+                .map(|e| e.name)
+                .collect(),
+        });
+    }
+    Ok(Report {
+        dir: dir.to_owned(),
+        datasets,
+    })
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .datasets
+            .iter()
+            .map(|d| d.name.len())
+            .max()
+            .unwrap_or(0);
+        writeln!(f, "data directory: {}", self.dir.display())?;
+        for d in &self.datasets {
+            write!(
+                f,
+                "  {:width$}  {}  ({}",
+                d.name,
+                d.status,
+                d.note,
+                width = width
+            )?;
+            if !d.used_by.is_empty() {
+                write!(f, "; used by {}", d.used_by.join(", "))?;
+            }
+            writeln!(f, ")")?;
+        }
+        let missing = self.datasets.iter().filter(|d| d.missing).count();
+        if missing > 0 {
+            write!(
+                f,
+                "{} dataset(s) missing: put the course files or a .tar of them into {} and run `cargo run data extract`",
+                missing,
+                self.dir.display()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Error for an entry point about to read a dataset that isn't there.
+pub fn missing_error(name: &str, dir: &Path) -> String {
+    let mut message = format!("{} is missing", dir.join(name).display());
+    if let Some(dataset) = find(name) {
+        message.push_str(&format!(" ({})", dataset.note));
+    }
+    message.push_str("; see `cargo run data`, or point --input or --data-root somewhere else");
+    message
+}
+
+// Entry points read their first dataset unless given --input, so that one had better be there.
+pub fn check_inputs(entry: &EntryPoint, args: &cli::Args) -> Result<(), cli::Error> {
+    match entry.datasets.first() {
+        Some(name) if args.input.is_none() => {
+            let dir = data_root()?;
+            if dir.join(name).exists() {
+                Ok(())
+            } else {
+                Err(missing_error(name, &dir).into())
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// For tests that need a course file: the path if it's there, otherwise an explanation on
+/// stderr and an early return, so a fresh clone without the big files still passes.
+#[macro_export]
+macro_rules! require_dataset {
+    ($name:expr) => {{
+        match $crate::dataset::data_root() {
+            Ok(dir) if dir.join($name).exists() => dir.join($name),
+            Ok(dir) => {
+                eprintln!("skipping: {}", $crate::dataset::missing_error($name, &dir));
+                return;
+            }
+            Err(e) => {
+                eprintln!("skipping: {}", e);
+                return;
+            }
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dataset-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // Minimal ustar writer, just enough to test the reader.
+    fn tar(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (name, contents) in entries {
+            let mut header = [0u8; 512];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[100..107].copy_from_slice(b"0000644");
+            header[124..135].copy_from_slice(format!("{:011o}", contents.len()).as_bytes());
+            header[156] = b'0';
+            header[257..263].copy_from_slice(b"ustar\0");
+            header[148..156].copy_from_slice(b"        ");
+            let sum: u32 = header.iter().map(|&b| b as u32).sum();
+            header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+            out.extend_from_slice(&header);
+            out.extend_from_slice(contents);
+            out.resize(out.len().div_ceil(512) * 512, 0);
+        }
+        out.resize(out.len() + 1024, 0);
+        out
+    }
+
+    #[test]
+    fn crc32_known_values() {
+        let dir = scratch_dir("crc");
+        fs::write(dir.join("empty"), b"").unwrap();
+        fs::write(dir.join("digits"), b"123456789").unwrap();
+        assert_eq!(crc32_file(&dir.join("empty")).unwrap(), 0);
+        assert_eq!(crc32_file(&dir.join("digits")).unwrap(), 0xcbf4_3926);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn catalogue_covers_entry_points() {
+        for entry in crate::ENTRY_POINTS.iter() {
+            for dataset in entry.datasets {
+                assert!(find(dataset).is_some(), "{} not in catalogue", dataset);
+            }
+        }
+    }
+
+    #[test]
+    fn checked_in_datasets_are_intact() {
+        let dir = match data_root() {
+            Ok(dir) => dir,
+            Err(e) => return eprintln!("skipping: {}", e),
+        };
+        for dataset in CATALOGUE.iter().filter(|d| d.crc32.is_some()) {
+            match dataset.status_in(&dir).unwrap() {
+                Status::Ok => {}
+                Status::Missing => eprintln!("skipping: {}", missing_error(dataset.name, &dir)),
+                other => panic!("{}: {}", dataset.name, other),
+            }
+        }
+    }
+
+    #[test]
+    fn explicit_data_root_wins() {
+        let dir = scratch_dir("root");
+        let missing = dir.join("nope");
+        assert_eq!(find_data_root(Some(&dir), Some(&missing)), Ok(dir.clone()));
+        assert_eq!(find_data_root(None, Some(&dir)), Ok(dir.clone()));
+        let e = find_data_root(Some(&missing), Some(&dir)).unwrap_err();
+        assert_eq!(
+            e.tried,
+            vec![Tried {
+                how: "--data-root",
+                path: missing.clone(),
+                problem: "not a directory",
+            }]
+        );
+        assert!(e.to_string().contains(&missing.display().to_string()));
+        // Nothing explicit: falls back to the repo this test runs in
+        assert!(find_data_root(None, None).unwrap().ends_with("data"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn extracts_catalogued_files_only() {
+        let dir = scratch_dir("extract");
+        let archive = dir.join("course.tar");
+        fs::write(
+            &archive,
+            tar(&[
+                ("stuff/kargerTriangle.txt", b"1 2 3\n2 1 3\n3 1 2\n"),
+                ("../../etc/passwd", b"nope"),
+                ("README", b"hello"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(archives_in(&dir).unwrap(), vec![archive.clone()]);
+        let extracted = extract_tar(&archive, &dir).unwrap();
+        assert_eq!(extracted, vec!["kargerTriangle.txt"]);
+        assert_eq!(
+            fs::read_to_string(dir.join("kargerTriangle.txt")).unwrap(),
+            "1 2 3\n2 1 3\n3 1 2\n"
+        );
+        assert!(!dir.join("passwd").exists() && !dir.join("README").exists());
+        let status = find("kargerTriangle.txt").unwrap().status_in(&dir).unwrap();
+        assert!(matches!(status, Status::WrongSize { .. }));
+        assert_eq!(
+            find("SCC.txt").unwrap().status_in(&dir).unwrap(),
+            Status::Missing
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_garbage_archives() {
+        let dir = scratch_dir("garbage");
+        let archive = dir.join("garbage.tar");
+        fs::write(&archive, vec![b'x'; 1024]).unwrap();
+        assert_eq!(
+            extract_tar(&archive, &dir).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,247 @@
+use crate::cli;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+pub type EntryFn = fn(&cli::Args) -> Result<Box<dyn Answer>, cli::Error>;
+
+/// Whatever an entry point computed: printable for people, and comparable as JSON for `verify`.
+pub trait Answer: fmt::Display {
+    fn to_json(&self) -> serde_json::Result<serde_json::Value>;
+}
+
+impl<T: Serialize + fmt::Display> Answer for T {
+    fn to_json(&self) -> serde_json::Result<serde_json::Value> {
+        serde_json::to_value(self)
+    }
+}
+
+// Which part of the course an entry point belongs to, in course order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Category {
+    Introduction,
+    Hw1,
+    Hw2,
+    Hw3,
+    Hw4,
+    Hw5,
+    Hw6,
+    Howto,
+    Misc,
+}
+
+impl Category {
+    pub const ALL: [Category; 9] = [
+        Category::Introduction,
+        Category::Hw1,
+        Category::Hw2,
+        Category::Hw3,
+        Category::Hw4,
+        Category::Hw5,
+        Category::Hw6,
+        Category::Howto,
+        Category::Misc,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Introduction => "introduction",
+            Category::Hw1 => "hw1",
+            Category::Hw2 => "hw2",
+            Category::Hw3 => "hw3",
+            Category::Hw4 => "hw4",
+            Category::Hw5 => "hw5",
+            Category::Hw6 => "hw6",
+            Category::Howto => "howto",
+            Category::Misc => "misc",
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Category {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Category::ALL
+            .iter()
+            .find(|c| c.name().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| format!("no category {:?}", s))
+    }
+}
+
+/// What `entry_point!` registers: the function plus enough metadata for `list` and `help`.
+pub struct EntryPoint {
+    pub name: &'static str,
+    pub run: EntryFn,
+    pub category: Category,
+    pub description: &'static str,
+    // File names under data/ that the entry point reads by default
+    pub datasets: &'static [&'static str],
+    // (usage, explanation) pairs, e.g. ("--input FILE", "...")
+    pub params: &'static [(&'static str, &'static str)],
+}
+
+impl EntryPoint {
+    pub fn usage(&self) -> String {
+        let mut usage = format!("cargo run {}", self.name);
+        for (param, _) in self.params {
+            usage.push_str(&format!(" [{}]", param));
+        }
+        usage
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let params: Vec<serde_json::Value> = self
+            .params
+            .iter()
+            .map(|(usage, explanation)| serde_json::json!({ "usage": usage, "explanation": explanation }))
+            .collect();
+        serde_json::json!({
+            "name": self.name,
+            "category": self.category.name(),
+            "description": self.description,
+            "datasets": self.datasets,
+            "params": params,
+        })
+    }
+
+    pub fn help(&self) -> String {
+        let mut help = format!("{} ({})\n", self.name, self.category);
+        if !self.description.is_empty() {
+            help.push_str(&format!("  {}\n", self.description));
+        }
+        help.push_str(&format!("\nusage:\n  {}\n", self.usage()));
+        if !self.params.is_empty() {
+            help.push_str("\nparameters:\n");
+            let width = self.params.iter().map(|(p, _)| p.len()).max().unwrap_or(0);
+            for (param, explanation) in self.params {
+                help.push_str(&format!(
+                    "  {:width$}  {}\n",
+                    param,
+                    explanation,
+                    width = width
+                ));
+            }
+        }
+        if !self.datasets.is_empty() {
+            help.push_str("\ndatasets:\n");
+            for dataset in self.datasets {
+                help.push_str(&format!("  data/{}\n", dataset));
+            }
+        }
+        help
+    }
+}
+
+// Entry points grouped by category, optionally only the one category.
+pub fn listing(entry_points: &[EntryPoint], only: Option<Category>) -> String {
+    let width = entry_points.iter().map(|e| e.name.len()).max().unwrap_or(0);
+    let mut listing = String::new();
+    for category in Category::ALL
+        .iter()
+        .filter(|&&c| only.is_none() || only == Some(c))
+    {
+        let mut members: Vec<&EntryPoint> = entry_points
+            .iter()
+            .filter(|e| e.category == *category)
+            .collect();
+        if members.is_empty() {
+            continue;
+        }
+        members.sort_by_key(|e| e.name);
+        listing.push_str(&format!("{}:\n", category));
+        for e in members {
+            listing.push_str(&format!(
+                "  {:width$}  {}\n",
+                e.name,
+                e.description,
+                width = width
+            ));
+        }
+    }
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop(_args: &cli::Args) -> Result<Box<dyn Answer>, cli::Error> {
+        Ok(Box::new(0))
+    }
+
+    const SAMPLE: [EntryPoint; 2] = [
+        EntryPoint {
+            name: "inversions",
+            run: noop,
+            category: Category::Hw1,
+            description: "Count inversions",
+            datasets: &["IntegerArray.txt"],
+            params: &[("--input FILE", "integers, one per line")],
+        },
+        EntryPoint {
+            name: "hello",
+            run: noop,
+            category: Category::Misc,
+            description: "",
+            datasets: &[],
+            params: &[],
+        },
+    ];
+
+    #[test]
+    fn categories_round_trip() {
+        for category in Category::ALL.iter() {
+            assert_eq!(category.name().parse::<Category>(), Ok(*category));
+        }
+        assert_eq!("HW4".parse::<Category>(), Ok(Category::Hw4));
+        assert!("hw7".parse::<Category>().is_err());
+    }
+
+    #[test]
+    fn help_mentions_everything() {
+        let help = SAMPLE[0].help();
+        assert!(help.starts_with("inversions (hw1)"));
+        assert!(help.contains("cargo run inversions [--input FILE]"));
+        assert!(help.contains("integers, one per line"));
+        assert!(help.contains("data/IntegerArray.txt"));
+        let json = SAMPLE[0].to_json();
+        assert_eq!(json["category"], "hw1");
+        assert_eq!(json["params"][0]["usage"], "--input FILE");
+    }
+
+    #[test]
+    fn answers_serialise() {
+        #[derive(Serialize)]
+        struct Cut {
+            min_cut: usize,
+        }
+        impl fmt::Display for Cut {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "min cut: {}", self.min_cut)
+            }
+        }
+        let answer: Box<dyn Answer> = Box::new(Cut { min_cut: 17 });
+        assert_eq!(answer.to_string(), "min cut: 17");
+        assert_eq!(
+            answer.to_json().unwrap(),
+            serde_json::json!({"min_cut": 17})
+        );
+    }
+
+    #[test]
+    fn listing_groups_by_category() {
+        let all = listing(&SAMPLE, None);
+        assert!(all.find("hw1:").unwrap() < all.find("misc:").unwrap());
+        let only = listing(&SAMPLE, Some(Category::Misc));
+        assert!(!only.contains("inversions"));
+        assert!(only.contains("hello"));
+    }
+}
@@ -1,5 +1,26 @@
-crate::entry_point!("hello", hello);
+use serde::Serialize;
+use std::fmt;
 
-fn hello() {
-    println!("Hello, world!");
+crate::entry_point!("hello", hello, {
+    category: Misc,
+    description: "Hello, world!",
+    datasets: [],
+    params: [],
+});
+
+fn hello(_args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
+    Ok(Box::new(Greeting {
+        greeting: "Hello, world!",
+    }))
+}
+
+#[derive(Debug, Serialize)]
+struct Greeting {
+    greeting: &'static str,
+}
+
+impl fmt::Display for Greeting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.greeting)
+    }
 }
@@ -0,0 +1,537 @@
+use crate::cli;
+use fxhash::FxHashMap as HashMap;
+use fxhash::FxHashSet as HashSet;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The input formats of the course files, see `dataset::CATALOGUE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    // One integer per line: IntegerArray, QuickSort, Median, the 2-sum file
+    Integers,
+    // Undirected adjacency lists, `v u u u`: kargerMinCut
+    Karger,
+    // Undirected weighted adjacency lists, `v u,w u,w`: dijkstraData
+    Dijkstra,
+    // Directed edges, `u v` per line, with planted SCCs: SCC
+    Scc,
+}
+
+impl FromStr for Kind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "integers" => Ok(Kind::Integers),
+            "karger" => Ok(Kind::Karger),
+            "dijkstra" => Ok(Kind::Dijkstra),
+            "scc" => Ok(Kind::Scc),
+            _ => Err(format!(
+                "unknown format {:?}, expected integers, karger, dijkstra or scc",
+                s
+            )),
+        }
+    }
+}
+
+/// `generate`'s flags. Not every flag means something for every format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerateOptions {
+    pub kind: Kind,
+    pub seed: u64,
+    // Integers in the file, or nodes in the graph
+    pub size: usize,
+    // Edges in the graph (defaults depend on the format)
+    pub edges: Option<usize>,
+    // Share of the integers that are distinct, the rest repeat them
+    pub distinct: f64,
+    // Share of the integers already in sorted position
+    pub sorted: f64,
+    // Inclusive range the integers are drawn from, 1..=size by default
+    pub range: Option<(i64, i64)>,
+    pub max_weight: u32,
+    // Sizes of the SCCs to plant; everything else ends up in an SCC of its own
+    pub sccs: Option<Vec<usize>>,
+    pub output: Option<PathBuf>,
+}
+
+impl GenerateOptions {
+    pub fn new(kind: Kind, size: usize) -> Self {
+        GenerateOptions {
+            kind,
+            seed: 0,
+            size,
+            edges: None,
+            distinct: 1.0,
+            sorted: 0.0,
+            range: None,
+            max_weight: 10000,
+            sccs: None,
+            output: None,
+        }
+    }
+
+    pub fn parse<I: IntoIterator<Item = String>>(argv: I) -> Result<Self, cli::Error> {
+        let mut argv = argv.into_iter();
+        let kind: Kind = argv
+            .next()
+            .ok_or("which format? integers, karger, dijkstra or scc")?
+            .parse()?;
+        let mut options = GenerateOptions::new(kind, 1000);
+        let (mut min, mut max) = (None, None);
+        while let Some(arg) = argv.next() {
+            let value = argv
+                .next()
+                .ok_or_else(|| cli::ArgsError::MissingValue(arg.clone()))?;
+            let invalid = || cli::ArgsError::InvalidValue {
+                flag: arg.clone(),
+                value: value.clone(),
+            };
+            let share = || match value.parse::<f64>() {
+                Ok(x) if (0.0..=1.0).contains(&x) => Ok(x),
+                _ => Err(invalid()),
+            };
+            match arg.as_str() {
+                "--seed" => options.seed = value.parse().map_err(|_| invalid())?,
+                "--size" => options.size = value.parse().map_err(|_| invalid())?,
+                "--edges" => options.edges = Some(value.parse().map_err(|_| invalid())?),
+                "--distinct" => options.distinct = share()?,
+                "--sorted" => options.sorted = share()?,
+                "--min" => min = Some(value.parse().map_err(|_| invalid())?),
+                "--max" => max = Some(value.parse().map_err(|_| invalid())?),
+                "--max-weight" => options.max_weight = value.parse().map_err(|_| invalid())?,
+                "--sccs" => {
+                    let sizes: Result<Vec<usize>, _> =
+                        value.split(',').map(|x| x.trim().parse()).collect();
+                    options.sccs = Some(sizes.map_err(|_| invalid())?);
+                }
+                "--output" => options.output = Some(PathBuf::from(value)),
+                _ => return Err(cli::ArgsError::UnknownFlag(arg).into()),
+            }
+        }
+        if min.is_some() || max.is_some() {
+            options.range = Some((min.unwrap_or(1), max.unwrap_or(options.size as i64)));
+        }
+        Ok(options)
+    }
+}
+
+/// What got generated, for the record.
+#[derive(Debug, Serialize)]
+pub struct Generated {
+    pub format: String,
+    pub seed: u64,
+    pub size: usize,
+    pub lines: usize,
+    pub edges: Option<usize>,
+    // Largest first, only for scc
+    pub planted_sccs: Option<Vec<usize>>,
+    pub output: Option<PathBuf>,
+}
+
+impl fmt::Display for Generated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} lines of {} (size {}, seed {})",
+            self.lines, self.format, self.size, self.seed
+        )?;
+        if let Some(edges) = self.edges {
+            write!(f, ", {} edges", edges)?;
+        }
+        if let Some(sccs) = &self.planted_sccs {
+            write!(f, ", planted SCCs {:?}", sccs)?;
+        }
+        if let Some(output) = &self.output {
+            write!(f, " in {}", output.display())?;
+        }
+        Ok(())
+    }
+}
+
+// Writes to --output, or stdout when there isn't one.
+pub fn generate(options: &GenerateOptions) -> Result<Generated, cli::Error> {
+    match &options.output {
+        Some(path) => generate_into(options, &mut BufWriter::new(File::create(path)?)),
+        None => generate_into(options, &mut BufWriter::new(io::stdout().lock())),
+    }
+}
+
+pub fn generate_into(
+    options: &GenerateOptions,
+    out: &mut impl Write,
+) -> Result<Generated, cli::Error> {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut generated = Generated {
+        format: format!("{:?}", options.kind).to_lowercase(),
+        seed: options.seed,
+        size: options.size,
+        lines: 0,
+        edges: None,
+        planted_sccs: None,
+        output: options.output.clone(),
+    };
+    match options.kind {
+        Kind::Integers => {
+            for x in integers(options, &mut rng)? {
+                writeln!(out, "{}", x)?;
+                generated.lines += 1;
+            }
+        }
+        Kind::Karger | Kind::Dijkstra => {
+            let n = options.size;
+            let max_edges = n * n.saturating_sub(1) / 2;
+            let m = options.edges.unwrap_or_else(|| max_edges.min(12 * n));
+            let adjacency = connected_graph(n, m, &mut rng)?;
+            // Same weight both ways: drawn when the edge is first seen from its smaller end
+            let mut weights: HashMap<(usize, usize), u32> = HashMap::default();
+            for (v, neighbours) in adjacency.iter().enumerate() {
+                write!(out, "{}", v + 1)?;
+                for &u in neighbours {
+                    if options.kind == Kind::Karger {
+                        write!(out, "\t{}", u + 1)?;
+                        continue;
+                    }
+                    let w = *weights
+                        .entry((v.min(u), v.max(u)))
+                        .or_insert_with(|| rng.gen_range(1..=options.max_weight.max(1)));
+                    write!(out, "\t{},{}", u + 1, w)?;
+                }
+                writeln!(out, "\t")?;
+                generated.lines += 1;
+            }
+            generated.edges = Some(m);
+        }
+        Kind::Scc => {
+            let n = options.size;
+            let planted = options.sccs.clone().unwrap_or_else(|| vec![n / 2]);
+            let edges = planted_scc_edges(n, &planted, options.edges, &mut rng)?;
+            for (u, v) in &edges {
+                writeln!(out, "{} {}", u + 1, v + 1)?;
+            }
+            generated.lines = edges.len();
+            generated.edges = Some(edges.len());
+            let mut sizes: Vec<usize> = planted.into_iter().filter(|&k| k > 0).collect();
+            sizes.sort_unstable_by(|a, b| b.cmp(a));
+            generated.planted_sccs = Some(sizes);
+        }
+    }
+    out.flush()?;
+    Ok(generated)
+}
+
+// `distinct` of them different, the rest copies of those; `sorted` of them in place and the
+// others shuffled among themselves.
+pub fn integers(options: &GenerateOptions, rng: &mut StdRng) -> Result<Vec<i64>, cli::Error> {
+    let n = options.size;
+    let (min, max) = options.range.unwrap_or((1, n.max(1) as i64));
+    if min > max {
+        return Err(format!("empty range {}..={}", min, max).into());
+    }
+    let span = (max as i128 - min as i128 + 1) as u128;
+    let distinct = ((n as f64 * options.distinct).ceil() as usize).clamp(n.min(1), n);
+    if distinct as u128 > span {
+        return Err(format!(
+            "can't fit {} distinct values in {}..={}",
+            distinct, min, max
+        )
+        .into());
+    }
+    let pool: Vec<i64> = if span <= usize::MAX as u128 {
+        rand::seq::index::sample(rng, span as usize, distinct)
+            .into_iter()
+            .map(|i| min + i as i64)
+            .collect()
+    } else {
+        // The whole of i64: collisions are not worth worrying about, but check anyway.
+        let mut seen = HashSet::default();
+        while seen.len() < distinct {
+            seen.insert(rng.gen::<i64>());
+        }
+        seen.into_iter().collect()
+    };
+    let mut values = pool.clone();
+    values.extend((distinct..n).map(|_| pool[rng.gen_range(0..pool.len())]));
+    values.sort_unstable();
+    let unsorted = ((1.0 - options.sorted) * n as f64).round() as usize;
+    let mut positions: Vec<usize> = rand::seq::index::sample(rng, n, unsorted).into_vec();
+    positions.sort_unstable();
+    let mut moved: Vec<i64> = positions.iter().map(|&i| values[i]).collect();
+    moved.shuffle(rng);
+    for (&i, x) in positions.iter().zip(moved) {
+        values[i] = x;
+    }
+    Ok(values)
+}
+
+// Simple connected undirected graph with `m` edges, as sorted adjacency lists.
+pub fn connected_graph(
+    n: usize,
+    m: usize,
+    rng: &mut StdRng,
+) -> Result<Vec<Vec<usize>>, cli::Error> {
+    let max_edges = n * n.saturating_sub(1) / 2;
+    if m + 1 < n || m > max_edges {
+        return Err(format!(
+            "a simple connected graph on {} nodes has {}..={} edges, not {}",
+            n,
+            n.saturating_sub(1),
+            max_edges,
+            m
+        )
+        .into());
+    }
+    let mut edges: HashSet<(usize, usize)> = HashSet::default();
+    // Random spanning tree first, so that it's connected
+    let mut order: Vec<usize> = (0..n).collect();
+    order.shuffle(rng);
+    for i in 1..n {
+        let (a, b) = (order[i], order[rng.gen_range(0..i)]);
+        edges.insert((a.min(b), a.max(b)));
+    }
+    // Then random extra edges. Near-complete graphs are cheaper to get by removing edges.
+    if m > max_edges / 2 {
+        let mut all: Vec<(usize, usize)> = (0..n)
+            .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+            .filter(|e| !edges.contains(e))
+            .collect();
+        all.shuffle(rng);
+        let missing = m - edges.len();
+        edges.extend(all.into_iter().take(missing));
+    } else {
+        while edges.len() < m {
+            let (a, b) = (rng.gen_range(0..n), rng.gen_range(0..n));
+            if a != b {
+                edges.insert((a.min(b), a.max(b)));
+            }
+        }
+    }
+    let mut adjacency = vec![Vec::new(); n];
+    for (a, b) in edges {
+        adjacency[a].push(b);
+        adjacency[b].push(a);
+    }
+    for neighbours in &mut adjacency {
+        neighbours.sort_unstable();
+    }
+    Ok(adjacency)
+}
+
+// Directed edges whose SCCs are exactly `planted` plus singletons. Each planted SCC is a cycle
+// with extra chords; between components edges only go forward in a random topological order,
+// so nothing merges. Every node gets at least one edge, or it would vanish from an edge list.
+pub fn planted_scc_edges(
+    n: usize,
+    planted: &[usize],
+    edges: Option<usize>,
+    rng: &mut StdRng,
+) -> Result<Vec<(usize, usize)>, cli::Error> {
+    let planted: Vec<usize> = planted.iter().copied().filter(|&k| k > 0).collect();
+    let in_planted: usize = planted.iter().sum();
+    if in_planted > n {
+        return Err(format!("planted SCCs have {} nodes, more than {}", in_planted, n).into());
+    }
+    // Component sizes in topological order
+    let mut sizes = planted;
+    sizes.extend(std::iter::repeat_n(1, n - in_planted));
+    sizes.shuffle(rng);
+    let mut nodes: Vec<usize> = (0..n).collect();
+    nodes.shuffle(rng);
+    let mut components = Vec::with_capacity(sizes.len());
+    let mut component_of = vec![0; n];
+    let mut start = 0;
+    for (c, &k) in sizes.iter().enumerate() {
+        let members = &nodes[start..start + k];
+        for &v in members {
+            component_of[v] = c;
+        }
+        components.push(members);
+        start += k;
+    }
+    let mut result = Vec::new();
+    for members in &components {
+        if members.len() > 1 {
+            for (i, &v) in members.iter().enumerate() {
+                result.push((v, members[(i + 1) % members.len()]));
+            }
+        }
+    }
+    for pair in components.windows(2) {
+        let from = pair[0][rng.gen_range(0..pair[0].len())];
+        let to = pair[1][rng.gen_range(0..pair[1].len())];
+        result.push((from, to));
+    }
+    let m = edges.unwrap_or(5 * n).max(result.len());
+    // Filler edges are never loops, and with one node there's nowhere else for them to go
+    if n < 2 && m > result.len() {
+        return Err(format!("{} edges without loops need at least 2 nodes, not {}", m, n).into());
+    }
+    while result.len() < m {
+        let (mut u, mut v) = (rng.gen_range(0..n), rng.gen_range(0..n));
+        if u == v {
+            continue;
+        }
+        if component_of[u] > component_of[v] {
+            std::mem::swap(&mut u, &mut v);
+        }
+        result.push((u, v));
+    }
+    result.shuffle(rng);
+    Ok(result)
+}
+
+// What `generate scc` would write, as edges instead of text: 1-indexed like the file. For
+// tests elsewhere that want a big graph with known SCCs.
+#[cfg(test)]
+pub fn scc_edges(size: usize, sccs: &[usize], edges: usize, seed: u64) -> Vec<(usize, usize)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    planted_scc_edges(size, sccs, Some(edges), &mut rng)
+        .unwrap()
+        .into_iter()
+        .map(|(u, v)| (u + 1, v + 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::formats;
+    use crate::graph::parse::{Line, Mode, ParseError, Records};
+    use crate::graph::Graph;
+    use std::path::Path;
+
+    fn text(options: &GenerateOptions) -> String {
+        let mut out = Vec::new();
+        generate_into(options, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    // What was generated, read back the way a file of it would be
+    fn lines<R>(text: String, parse: fn(&Line<'_>) -> Result<R, ParseError>) -> Records<R> {
+        Records::new(Path::new("x"), io::Cursor::new(text), Mode::Strict, parse)
+    }
+
+    #[test]
+    fn same_seed_same_bytes() {
+        for kind in [Kind::Integers, Kind::Karger, Kind::Dijkstra, Kind::Scc] {
+            let mut options = GenerateOptions::new(kind, 50);
+            options.seed = 7;
+            assert_eq!(text(&options), text(&options), "{:?}", kind);
+            let mut other = options.clone();
+            other.seed = 8;
+            assert_ne!(text(&options), text(&other), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn integers_have_the_requested_shape() {
+        let mut options = GenerateOptions::new(Kind::Integers, 1000);
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut permutation = integers(&options, &mut rng).unwrap();
+        permutation.sort_unstable();
+        assert_eq!(permutation, (1..=1000).collect::<Vec<i64>>());
+
+        options.distinct = 0.25;
+        options.sorted = 0.9;
+        options.range = Some((-1_000_000, 1_000_000));
+        let values = integers(&options, &mut rng).unwrap();
+        assert_eq!(values.len(), 1000);
+        assert_eq!(values.iter().collect::<HashSet<_>>().len(), 250);
+        assert!(values.iter().all(|x| x.abs() <= 1_000_000));
+        let mut sorted = values.clone();
+        sorted.sort_unstable();
+        let in_place = values.iter().zip(&sorted).filter(|(a, b)| a == b).count();
+        assert!(in_place >= 900, "{} in place", in_place);
+
+        options.range = Some((1, 10));
+        assert!(integers(&options, &mut rng).is_err());
+    }
+
+    #[test]
+    fn karger_and_dijkstra_graphs_are_symmetric() {
+        let mut options = GenerateOptions::new(Kind::Karger, 30);
+        options.edges = Some(100);
+        let karger = text(&options);
+        let adjacency: Vec<Vec<usize>> = karger
+            .lines()
+            .map(|l| {
+                l.split_whitespace()
+                    .skip(1)
+                    .map(|x| x.parse().unwrap())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(adjacency.len(), 30);
+        assert_eq!(adjacency.iter().map(Vec::len).sum::<usize>(), 200);
+        for (v, neighbours) in adjacency.iter().enumerate() {
+            for &u in neighbours {
+                assert!(adjacency[u - 1].contains(&(v + 1)));
+            }
+        }
+
+        options.kind = Kind::Dijkstra;
+        options.max_weight = 50;
+        let lines = lines(text(&options), formats::weighted_adjacency_line);
+        let (graph, _) = crate::aa_dijkstra::read_graph(lines).unwrap();
+        assert_eq!(graph.node_count(), 30);
+        assert_eq!(graph.edge_count(), 200);
+        for e in graph.edge_indices() {
+            let (a, b) = graph.edge_endpoints(e).unwrap();
+            let back = graph.find_edge(b, a).unwrap();
+            assert_eq!(graph[e], graph[back]);
+            assert!((1..=50).contains(&graph[e]));
+        }
+        assert!(GenerateOptions::parse(vec!["nope".to_string()]).is_err());
+        assert!(connected_graph(30, 1000, &mut StdRng::seed_from_u64(0)).is_err());
+        assert_eq!(
+            connected_graph(30, 435, &mut StdRng::seed_from_u64(0)).unwrap()[0].len(),
+            29
+        );
+    }
+
+    #[test]
+    fn too_small_for_any_edges() {
+        let options = GenerateOptions::parse("scc --size 1".split(' ').map(String::from)).unwrap();
+        let e = generate_into(&options, &mut Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "5 edges without loops need at least 2 nodes, not 1"
+        );
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(planted_scc_edges(0, &[], None, &mut rng).unwrap(), vec![]);
+        assert!(planted_scc_edges(1, &[1], Some(1), &mut rng).is_err());
+    }
+
+    #[test]
+    fn planted_sccs_are_found() {
+        let options = GenerateOptions::parse(
+            "scc --size 500 --sccs 120,60,7,2 --edges 3000 --seed 3"
+                .split(' ')
+                .map(String::from),
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        let generated = generate_into(&options, &mut out).unwrap();
+        assert_eq!(generated.planted_sccs, Some(vec![120, 60, 7, 2]));
+        assert_eq!(generated.lines, 3000);
+        let mut graph: Graph<usize> = Graph::new();
+        graph
+            .add_directed_edges(lines(String::from_utf8(out).unwrap(), formats::edge_line))
+            .unwrap();
+        assert_eq!(graph.nodes.len(), 500);
+        let mut sizes: Vec<usize> = crate::aa_kosajaru::kosaraju(&graph)
+            .iter()
+            .map(|scc| scc.nodes.len())
+            .collect();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(&sizes[..5], &[120, 60, 7, 2, 1]);
+        assert_eq!(sizes.len(), 500 - 120 - 60 - 7 - 2 + 4);
+    }
+}
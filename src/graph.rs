@@ -1,112 +1,609 @@
+#![allow(dead_code)]
+
 use fxhash::FxHashMap as HashMap;
 use fxhash::FxHashSet as HashSet;
+use parse::{LoadError, Mode, ParseError, Records};
 use std::clone::Clone;
 use std::collections::VecDeque;
-use std::fs::File;
 use std::hash::Hash;
-use std::io::{BufRead, BufReader};
 use std::str::FromStr;
 
+pub mod bfs;
+pub mod biconnected;
+pub mod bipartite;
+pub mod builder;
+pub mod convert;
+pub mod csr;
+pub mod dfs;
+pub mod formats;
+pub mod parse;
+pub mod reachability;
+pub mod scc;
+pub mod toposort;
+
+/// A node and both of its adjacency lists. Edges carry a payload `W` (a weight, a label, or
+/// `()` for none), kept in `weights` next to `edges` and in `inverse_weights` next to
+/// `inverse_edges`: `edges[i]` has payload `weights[i]`.
 #[derive(Debug, PartialEq, Eq)]
-pub struct Node<T> {
+pub struct Node<T, W = ()> {
     pub id: T,
     pub edges: Vec<T>,
     pub inverse_edges: Vec<T>,
+    pub weights: Vec<W>,
+    pub inverse_weights: Vec<W>,
     pub seen: bool,
     pub processed: bool,
 }
 
 impl<T> Node<T> {
     pub fn new(id: T, edges: Vec<T>, inverse_edges: Vec<T>) -> Self {
+        let weights = vec![(); edges.len()];
+        let inverse_weights = vec![(); inverse_edges.len()];
+        Self {
+            id,
+            edges,
+            inverse_edges,
+            weights,
+            inverse_weights,
+            seen: false,
+            processed: false,
+        }
+    }
+}
+
+impl<T, W> Node<T, W> {
+    pub fn with_weights(id: T, edges: Vec<(T, W)>, inverse_edges: Vec<(T, W)>) -> Self {
+        let (edges, weights) = edges.into_iter().unzip();
+        let (inverse_edges, inverse_weights) = inverse_edges.into_iter().unzip();
         Self {
             id,
             edges,
             inverse_edges,
+            weights,
+            inverse_weights,
             seen: false,
             processed: false,
         }
     }
+
+    // (target, payload) of every outgoing edge
+    pub fn weighted_edges(&self) -> impl Iterator<Item = (&T, &W)> {
+        self.edges.iter().zip(&self.weights)
+    }
+
+    // (source, payload) of every incoming edge
+    pub fn weighted_inverse_edges(&self) -> impl Iterator<Item = (&T, &W)> {
+        self.inverse_edges.iter().zip(&self.inverse_weights)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
-pub struct Graph<T: Clone + Eq + Hash> {
-    pub nodes: HashMap<T, Node<T>>,
+pub struct Graph<T: Clone + Eq + Hash, W = ()> {
+    pub nodes: HashMap<T, Node<T, W>>,
 }
 
-impl<T> Graph<T>
-where
-    T: FromStr + Clone + Eq + Hash,
-    <T as FromStr>::Err: std::fmt::Debug,
-{
+impl<T: Clone + Eq + Hash, W> Graph<T, W> {
     pub fn new() -> Self {
         Self {
             nodes: HashMap::default(),
         }
     }
-    pub fn from_nodes(nodes: HashMap<T, Node<T>>) -> Self {
+    // Takes the map as it is; see `try_from_nodes` for one that gets checked.
+    pub fn from_nodes(nodes: HashMap<T, Node<T, W>>) -> Self {
         Self { nodes }
     }
+    pub fn try_from_nodes(nodes: HashMap<T, Node<T, W>>) -> Result<Self, Inconsistency<T>> {
+        let graph = Self { nodes };
+        graph.validate()?;
+        Ok(graph)
+    }
     pub fn new_node(&mut self, id: T) {
         if !self.nodes.contains_key(&id) {
-            self.nodes
-                .insert(id.clone(), Node::new(id.clone(), Vec::new(), Vec::new()));
+            self.nodes.insert(
+                id.clone(),
+                Node::with_weights(id.clone(), Vec::new(), Vec::new()),
+            );
         }
     }
     pub fn add_node(&mut self, node: &T) {
         self.new_node(node.clone());
     }
-    pub fn add_edge(&mut self, from: T, to: T) {
+
+    pub fn edge_count(&self) -> usize {
+        self.nodes.values().map(|n| n.edges.len()).sum()
+    }
+
+    // How many parallel `from -> to` edges there are
+    pub fn edge_multiplicity(&self, from: &T, to: &T) -> usize {
+        self.nodes
+            .get(from)
+            .map_or(0, |n| n.edges.iter().filter(|e| *e == to).count())
+    }
+
+    pub fn has_edge(&self, from: &T, to: &T) -> bool {
+        self.edge_multiplicity(from, to) > 0
+    }
+
+    // Payload of the first `from -> to` edge
+    pub fn weight(&self, from: &T, to: &T) -> Option<&W> {
+        self.nodes
+            .get(from)?
+            .weighted_edges()
+            .find(|(e, _)| *e == to)
+            .map(|(_, w)| w)
+    }
+
+    // Removes the node and every edge into or out of it, and hands the node back.
+    pub fn remove_node(&mut self, id: &T) -> Option<Node<T, W>> {
+        let node = self.nodes.remove(id)?;
+        for to in &node.edges {
+            if let Some(other) = self.nodes.get_mut(to) {
+                retain_pairs(&mut other.inverse_edges, &mut other.inverse_weights, |e| {
+                    e != id
+                });
+            }
+        }
+        for from in &node.inverse_edges {
+            if let Some(other) = self.nodes.get_mut(from) {
+                retain_pairs(&mut other.edges, &mut other.weights, |e| e != id);
+            }
+        }
+        Some(node)
+    }
+
+    // Every edge turned around, in place, payloads and all
+    pub fn reverse(&mut self) {
+        for node in self.nodes.values_mut() {
+            std::mem::swap(&mut node.edges, &mut node.inverse_edges);
+            std::mem::swap(&mut node.weights, &mut node.inverse_weights);
+        }
+    }
+
+    // Checks that the map keys match the node ids, that every edge ends at a node, that each
+    // list has a payload per edge, and that `edges` and `inverse_edges` list the same edges,
+    // parallel ones as often.
+    pub fn validate(&self) -> Result<(), Inconsistency<T>> {
+        let mut balance: HashMap<(&T, &T), i64> = HashMap::default();
+        for (key, node) in &self.nodes {
+            if *key != node.id {
+                return Err(Inconsistency::WrongKey {
+                    key: key.clone(),
+                    id: node.id.clone(),
+                });
+            }
+            if node.weights.len() != node.edges.len()
+                || node.inverse_weights.len() != node.inverse_edges.len()
+            {
+                return Err(Inconsistency::MissingWeights { id: key.clone() });
+            }
+            for to in &node.edges {
+                if !self.nodes.contains_key(to) {
+                    return Err(Inconsistency::MissingNode {
+                        from: key.clone(),
+                        to: to.clone(),
+                    });
+                }
+                *balance.entry((key, to)).or_default() += 1;
+            }
+            for from in &node.inverse_edges {
+                if !self.nodes.contains_key(from) {
+                    return Err(Inconsistency::MissingNode {
+                        from: from.clone(),
+                        to: key.clone(),
+                    });
+                }
+                *balance.entry((from, key)).or_default() -= 1;
+            }
+        }
+        match balance.into_iter().find(|(_, b)| *b != 0) {
+            Some(((from, to), _)) => Err(Inconsistency::Unmirrored {
+                from: from.clone(),
+                to: to.clone(),
+                forward: self.edge_multiplicity(from, to),
+                inverse: self.nodes[to]
+                    .inverse_edges
+                    .iter()
+                    .filter(|e| *e == from)
+                    .count(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: Clone + Eq + Hash, W: Clone> Graph<T, W> {
+    pub fn add_weighted_edge(&mut self, from: T, to: T, weight: W) {
         self.new_node(from.clone());
         self.new_node(to.clone());
-        self.nodes.get_mut(&from).unwrap().edges.push(to.clone());
-        self.nodes.get_mut(&to).unwrap().inverse_edges.push(from);
-    }
-    pub fn load_from_directed_edges_file(&mut self, path: &str) {
-        let file = File::open(path).unwrap();
-        let reader = BufReader::new(file);
-        for line in reader.lines() {
-            let line = line.unwrap();
-            let mut iter = line.split_whitespace();
-            let from = iter
-                .next()
-                .unwrap()
-                .parse::<T>()
-                .expect("Failed to parse 'from' node");
-            let to = iter
-                .next()
-                .unwrap()
-                .parse::<T>()
-                .expect("Failed to parse 'to' node");
+        let node = self.nodes.get_mut(&from).unwrap();
+        node.edges.push(to.clone());
+        node.weights.push(weight.clone());
+        let node = self.nodes.get_mut(&to).unwrap();
+        node.inverse_edges.push(from);
+        node.inverse_weights.push(weight);
+    }
+
+    // Merges `to` into `from` along a `from -> to` edge. Edges between the two (either way)
+    // disappear, everything else `to` had now belongs to `from`, parallel edges and payloads
+    // included. False, and nothing changes, if there is no such edge.
+    pub fn contract_edge(&mut self, from: &T, to: &T) -> bool {
+        if from == to || !self.has_edge(from, to) {
+            return false;
+        }
+        let merged = self.remove_node(to).unwrap();
+        let node = self.nodes.get_mut(from).unwrap();
+        retain_pairs(&mut node.edges, &mut node.weights, |e| e != to);
+        retain_pairs(&mut node.inverse_edges, &mut node.inverse_weights, |e| {
+            e != to
+        });
+        for (next, weight) in merged.weighted_edges().filter(|(e, _)| *e != from) {
+            let next = if next == to { from } else { next };
+            self.add_weighted_edge(from.clone(), next.clone(), weight.clone());
+        }
+        for (prev, weight) in merged
+            .weighted_inverse_edges()
+            .filter(|(e, _)| *e != from && *e != to)
+        {
+            self.add_weighted_edge(prev.clone(), from.clone(), weight.clone());
+        }
+        true
+    }
+
+    // The nodes in `keep` that are in the graph, and the edges among them.
+    pub fn induced_subgraph(&self, keep: impl IntoIterator<Item = T>) -> Graph<T, W> {
+        let keep: HashSet<T> = keep
+            .into_iter()
+            .filter(|id| self.nodes.contains_key(id))
+            .collect();
+        let mut graph = Graph::new();
+        for id in &keep {
+            graph.add_node(id);
+            for (to, weight) in self.nodes[id].weighted_edges() {
+                if keep.contains(to) {
+                    graph.add_weighted_edge(id.clone(), to.clone(), weight.clone());
+                }
+            }
+        }
+        graph
+    }
+}
+
+impl<T: Clone + Eq + Hash, W: PartialEq> Graph<T, W> {
+    // Removes one `from -> to` edge, if there is one, and returns its payload.
+    pub fn remove_edge(&mut self, from: &T, to: &T) -> Option<W> {
+        let node = self.nodes.get_mut(from)?;
+        let i = node.edges.iter().position(|e| e == to)?;
+        node.edges.remove(i);
+        let weight = node.weights.remove(i);
+        // The matching inverse entry: same source and payload, if parallel edges differ
+        let node = self.nodes.get_mut(to).unwrap();
+        let j = node
+            .weighted_inverse_edges()
+            .position(|(e, w)| e == from && *w == weight)
+            .or_else(|| node.inverse_edges.iter().position(|e| e == from))
+            .unwrap();
+        node.inverse_edges.remove(j);
+        node.inverse_weights.remove(j);
+        Some(weight)
+    }
+}
+
+impl<T: Clone + Eq + Hash, W: Clone + Default> Graph<T, W> {
+    // An edge with the default payload, i.e. just an edge for `Graph<T>`
+    pub fn add_edge(&mut self, from: T, to: T) {
+        self.add_weighted_edge(from, to, W::default());
+    }
+}
+
+// Keeps the ids `keep` says yes to, and the payloads next to them
+fn retain_pairs<T, W>(ids: &mut Vec<T>, weights: &mut Vec<W>, keep: impl FnMut(&T) -> bool) {
+    let keep: Vec<bool> = ids.iter().map(keep).collect();
+    let mut flags = keep.iter();
+    ids.retain(|_| *flags.next().unwrap());
+    let mut flags = keep.iter();
+    weights.retain(|_| *flags.next().unwrap());
+}
+
+/// What `Graph::validate` found wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inconsistency<T> {
+    // `nodes[key].id` is something else
+    WrongKey {
+        key: T,
+        id: T,
+    },
+    // An edge, forward or inverse, to a node that isn't in the map
+    MissingNode {
+        from: T,
+        to: T,
+    },
+    // `from -> to` is in `from`'s edges `forward` times but in `to`'s inverse_edges `inverse` times
+    Unmirrored {
+        from: T,
+        to: T,
+        forward: usize,
+        inverse: usize,
+    },
+    // A weights list isn't as long as its edges list
+    MissingWeights {
+        id: T,
+    },
+}
+
+impl<T: std::fmt::Debug> std::fmt::Display for Inconsistency<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Inconsistency::WrongKey { key, id } => {
+                write!(f, "node {:?} is stored under key {:?}", id, key)
+            }
+            Inconsistency::MissingNode { from, to } => {
+                write!(f, "edge {:?} -> {:?} ends outside the graph", from, to)
+            }
+            Inconsistency::Unmirrored {
+                from,
+                to,
+                forward,
+                inverse,
+            } => write!(
+                f,
+                "edge {:?} -> {:?} is in edges {} times but in inverse_edges {} times",
+                from, to, forward, inverse
+            ),
+            Inconsistency::MissingWeights { id } => {
+                write!(f, "node {:?} doesn't have a weight for every edge", id)
+            }
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::error::Error for Inconsistency<T> {}
+
+impl<T, W> Graph<T, W>
+where
+    T: FromStr + Clone + Eq + Hash,
+    W: Clone + Default,
+{
+    // Errs at the first bad line, or with `Mode::Lenient` skips it and returns it as a warning
+    pub fn load_from_directed_edges_file(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+        mode: Mode,
+    ) -> Result<Vec<ParseError>, LoadError> {
+        self.add_directed_edges(read_directed_edges(path, mode)?)
+    }
+
+    // The same from lines that come from anywhere
+    pub fn add_directed_edges(
+        &mut self,
+        mut edges: Records<(T, T)>,
+    ) -> Result<Vec<ParseError>, LoadError> {
+        for (from, to) in &mut edges {
             self.add_edge(from, to);
         }
+        edges.finish()
+    }
+}
+
+impl<T, W> Graph<T, W>
+where
+    T: FromStr + Clone + Eq + Hash,
+    W: FromStr + Clone,
+{
+    // dijkstraData.txt: `v u,w u,w ...` gives the edges v -> u with payload w. The file lists
+    // each undirected edge at both ends, so both directions end up in the graph.
+    pub fn load_from_weighted_adjacency_file(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+        mode: Mode,
+    ) -> Result<Vec<ParseError>, LoadError> {
+        self.add_weighted_adjacency(read_weighted_adjacency(path, mode)?)
+    }
+
+    pub fn add_weighted_adjacency(
+        &mut self,
+        mut lines: Records<formats::AdjacencyLine<T, W>>,
+    ) -> Result<Vec<ParseError>, LoadError> {
+        for (from, edges) in &mut lines {
+            self.add_node(&from);
+            for (to, weight) in edges {
+                self.add_weighted_edge(from.clone(), to, weight);
+            }
+        }
+        lines.finish()
+    }
+}
+
+// `from to` pairs, one per line, as they come; shared by every graph type that loads edge lists.
+// Call `finish` on what's left afterwards to see if the whole file made it.
+pub fn read_directed_edges<T: FromStr>(
+    path: impl AsRef<std::path::Path>,
+    mode: Mode,
+) -> Result<Records<(T, T)>, LoadError> {
+    Records::open(path, mode, formats::edge_line)
+}
+
+// `v u,w u,w ...` lines as (v, [(u, w), ...])
+pub fn read_weighted_adjacency<T: FromStr, W: FromStr>(
+    path: impl AsRef<std::path::Path>,
+    mode: Mode,
+) -> Result<Records<formats::AdjacencyLine<T, W>>, LoadError> {
+    Records::open(path, mode, formats::weighted_adjacency_line)
+}
+
+impl<T: Clone + Eq + Hash, W> Default for Graph<T, W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What the traversals need from a graph: its size and both adjacency lists of a node, as
+/// slices borrowed from the graph.
+pub trait Adjacency {
+    type Id: Clone + Eq + Hash;
+    fn node_count(&self) -> usize;
+    fn node_ids(&self) -> Vec<Self::Id>;
+    fn out_edges(&self, id: &Self::Id) -> &[Self::Id];
+    fn in_edges(&self, id: &Self::Id) -> &[Self::Id];
+}
+
+impl<T: Clone + Eq + Hash, W> Adjacency for Graph<T, W> {
+    type Id = T;
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+    fn node_ids(&self) -> Vec<T> {
+        self.nodes.keys().cloned().collect()
+    }
+    fn out_edges(&self, id: &T) -> &[T] {
+        &self.nodes[id].edges
     }
+    fn in_edges(&self, id: &T) -> &[T] {
+        &self.nodes[id].inverse_edges
+    }
+}
+
+/// Where a traversal goes from a node. The iterator borrows from the graph (and from the
+/// provider, which may keep state between calls), so visiting a node allocates nothing.
+pub trait Neighbours<G: Adjacency + ?Sized> {
+    type Iter<'a>: Iterator<Item = &'a G::Id>
+    where
+        Self: 'a,
+        G: 'a;
+    fn neighbours<'a>(&'a mut self, graph: &'a G, node: &'a G::Id) -> Self::Iter<'a>;
 }
 
-pub fn dfs_finish_ord<T, Y>(
-    graph: &mut Graph<T>,
-    trajectory: &VecDeque<T>,
-    neighbours: fn(&Node<T>) -> Vec<T>,
-    search: fn(&Node<T>) -> Option<Y>,
-) -> (Option<Y>, HashMap<T, i64>)
+// Along the edges
+pub struct Forward;
+
+// Against the edges, i.e. along inverse_edges
+pub struct Reverse;
+
+impl<G: Adjacency + ?Sized> Neighbours<G> for Forward {
+    type Iter<'a>
+        = std::slice::Iter<'a, G::Id>
+    where
+        G: 'a;
+    fn neighbours<'a>(&'a mut self, graph: &'a G, node: &'a G::Id) -> Self::Iter<'a> {
+        graph.out_edges(node).iter()
+    }
+}
+
+impl<G: Adjacency + ?Sized> Neighbours<G> for Reverse {
+    type Iter<'a>
+        = std::slice::Iter<'a, G::Id>
+    where
+        G: 'a;
+    fn neighbours<'a>(&'a mut self, graph: &'a G, node: &'a G::Id) -> Self::Iter<'a> {
+        graph.in_edges(node).iter()
+    }
+}
+
+// Both ways, edges then inverse_edges: the graph as if it were undirected. A pair of opposite
+// edges shows up twice, which traversals that skip seen nodes don't mind.
+pub struct Undirected;
+
+impl<G: Adjacency + ?Sized> Neighbours<G> for Undirected {
+    type Iter<'a>
+        = std::iter::Chain<std::slice::Iter<'a, G::Id>, std::slice::Iter<'a, G::Id>>
+    where
+        G: 'a;
+    fn neighbours<'a>(&'a mut self, graph: &'a G, node: &'a G::Id) -> Self::Iter<'a> {
+        graph
+            .out_edges(node)
+            .iter()
+            .chain(graph.in_edges(node).iter())
+    }
+}
+
+// So that a caller can hand over `&mut provider` and look at its state afterwards
+impl<G: Adjacency + ?Sized, N: Neighbours<G>> Neighbours<G> for &mut N {
+    type Iter<'a>
+        = N::Iter<'a>
+    where
+        Self: 'a,
+        G: 'a;
+    fn neighbours<'a>(&'a mut self, graph: &'a G, node: &'a G::Id) -> Self::Iter<'a> {
+        (**self).neighbours(graph, node)
+    }
+}
+
+/// `inner`'s neighbours, minus the edges `keep(from, to)` says no to. `keep` is `FnMut`, so it
+/// can count, remember or change its mind as the traversal goes.
+pub struct Filtered<N, F> {
+    pub inner: N,
+    pub keep: F,
+}
+
+pub struct FilteredIter<'a, I, F, T> {
+    inner: I,
+    keep: &'a mut F,
+    from: &'a T,
+}
+
+impl<'a, I, F, T> Iterator for FilteredIter<'a, I, F, T>
 where
-    T: Clone + Eq + Hash,
+    I: Iterator<Item = &'a T>,
+    F: FnMut(&T, &T) -> bool,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        let from = self.from;
+        let keep = &mut *self.keep;
+        self.inner.find(|to| keep(from, to))
+    }
+}
+
+impl<G, N, F> Neighbours<G> for Filtered<N, F>
+where
+    G: Adjacency + ?Sized,
+    N: Neighbours<G>,
+    F: FnMut(&G::Id, &G::Id) -> bool,
+{
+    type Iter<'a>
+        = FilteredIter<'a, N::Iter<'a>, F, G::Id>
+    where
+        Self: 'a,
+        G: 'a;
+    fn neighbours<'a>(&'a mut self, graph: &'a G, node: &'a G::Id) -> Self::Iter<'a> {
+        FilteredIter {
+            inner: self.inner.neighbours(graph, node),
+            keep: &mut self.keep,
+            from: node,
+        }
+    }
+}
+
+// Position of each node in reverse finish order. That's a topological order only if the graph
+// has no cycles, which this doesn't check; `toposort::toposort` does.
+pub fn dfs_finish_ord<G, N, S, Y>(
+    graph: &G,
+    trajectory: &VecDeque<G::Id>,
+    neighbours: N,
+    search: S,
+) -> (Option<Y>, HashMap<G::Id, i64>)
+where
+    G: Adjacency + ?Sized,
+    N: Neighbours<G>,
+    S: FnMut(&G::Id) -> Option<Y>,
 {
     let (y, finish_times) = dfs_finish_time(graph, trajectory, neighbours, search);
     let mut finish_order = HashMap::default();
     for (node, time) in finish_times {
-        finish_order.insert(node, graph.nodes.len() as i64 - time - 1);
+        finish_order.insert(node, graph.node_count() as i64 - time - 1);
     }
     (y, finish_order)
 }
 
-pub fn dfs_finish_time<T, Y>(
-    graph: &mut Graph<T>,
-    trajectory: &VecDeque<T>,
-    neighbours: fn(&Node<T>) -> Vec<T>,
-    search: fn(&Node<T>) -> Option<Y>,
-) -> (Option<Y>, HashMap<T, i64>)
+pub fn dfs_finish_time<G, N, S, Y>(
+    graph: &G,
+    trajectory: &VecDeque<G::Id>,
+    mut neighbours: N,
+    mut search: S,
+) -> (Option<Y>, HashMap<G::Id, i64>)
 where
-    T: Clone + Eq + Hash,
+    G: Adjacency + ?Sized,
+    N: Neighbours<G>,
+    S: FnMut(&G::Id) -> Option<Y>,
 {
     let mut finish_times = HashMap::default();
     let mut time = 0;
@@ -132,11 +629,9 @@ where
             if !seen.contains(&node) {
                 seen.insert(node.clone());
                 stack.push_back(node.clone());
-                let node = graph.nodes.get(&node).unwrap();
-                let neighbours = neighbours(node);
-                for neighbour in neighbours {
-                    if !seen.contains(&neighbour) {
-                        stack.push_back(neighbour);
+                for neighbour in neighbours.neighbours(graph, &node) {
+                    if !seen.contains(neighbour) {
+                        stack.push_back(neighbour.clone());
                     }
                 }
             } else {
@@ -144,7 +639,7 @@ where
                     finish_times.insert(node.clone(), time);
                     time += 1;
                 }
-                if let Some(y) = search(graph.nodes.get(&node).unwrap()) {
+                if let Some(y) = search(&node) {
                     result = Some(y);
                 }
             }
@@ -152,7 +647,7 @@ where
     }
 }
 
-pub fn irrel<T>(_node: &Node<T>) -> Option<bool> {
+pub fn irrel<T>(_node: &T) -> Option<bool> {
     None
 }
 
@@ -162,21 +657,33 @@ pub fn irrel<T>(_node: &Node<T>) -> Option<bool> {
 mod tests {
     use super::*;
 
+    // `text` as the lines of a dijkstraData.txt-style file called `x`
+    fn weighted_lines<T: FromStr, W: FromStr>(
+        text: &'static str,
+        mode: Mode,
+    ) -> Records<formats::AdjacencyLine<T, W>> {
+        Records::new(
+            std::path::Path::new("x"),
+            text.as_bytes(),
+            mode,
+            formats::weighted_adjacency_line,
+        )
+    }
+
     #[test]
     fn test_graph() {
+        let path = crate::require_dataset!("SCC.txt");
         let mut graph: Graph<usize> = Graph::new();
-        graph.load_from_directed_edges_file("data/SCC.txt");
+        graph
+            .load_from_directed_edges_file(path, Mode::Strict)
+            .unwrap();
         assert_eq!(graph.nodes.len(), 875714);
     }
 
     #[test]
     fn test_toposort_dfs() {
         // A simple graph with 4 vertices
-        let mut graph: Graph<usize> = Graph::new();
-        graph.add_edge(1, 2);
-        graph.add_edge(1, 3);
-        graph.add_edge(3, 4);
-        graph.add_edge(2, 4);
+        let graph: Graph<usize> = crate::graph! { 1 -> 2, 3; 3 -> 4; 2 -> 4 };
         // Get all possible trajectories as all possible transpositions of vec![1,2,3,4]
         let trajectories: Vec<VecDeque<usize>> = vec![
             vec![1, 2, 3, 4],
@@ -190,8 +697,7 @@ mod tests {
 
         //let trajectory: VecDeque<usize> = graph.nodes.keys().cloned().collect();
         for trajectory in trajectories {
-            let (_, processed) =
-                dfs_finish_ord(&mut graph, &trajectory, |node| node.edges.clone(), irrel);
+            let (_, processed) = dfs_finish_ord(&graph, &trajectory, Forward, irrel);
             // dbg!(&processed);
             assert!(processed.get(&1).unwrap() == &0);
             assert!(processed.get(&2).unwrap() == &1 || processed.get(&3).unwrap() == &1);
@@ -203,12 +709,199 @@ mod tests {
     #[test]
     fn test_toposort_dfs_terminates() {
         // Cyclic graph with 3 nodes
-        let mut graph: Graph<usize> = Graph::new();
-        graph.add_edge(1, 2);
-        graph.add_edge(2, 3);
-        graph.add_edge(3, 1);
+        let graph: Graph<usize> = crate::graph! { 1 -> 2; 2 -> 3; 3 -> 1 };
         let trajectory: VecDeque<usize> = graph.nodes.keys().cloned().collect();
-        let (result, _) = dfs_finish_ord(&mut graph, &trajectory, |node| node.edges.clone(), irrel);
+        let (result, _) = dfs_finish_ord(&graph, &trajectory, Forward, irrel);
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_stateful_and_reverse_neighbours() {
+        // 1 -> 2 -> 3 -> 4, plus a shortcut 1 -> 4
+        let graph: Graph<usize> = crate::graph! { 1 -> 2; 2 -> 3; 3 -> 4; 1 -> 4 };
+        let from_one: VecDeque<usize> = vec![1].into_iter().collect();
+        let from_four: VecDeque<usize> = vec![4].into_iter().collect();
+
+        // Drop the shortcut and count how many edges were looked at on the way
+        let mut looked_at = 0;
+        let mut no_shortcut = Filtered {
+            inner: Forward,
+            keep: |from: &usize, to: &usize| {
+                looked_at += 1;
+                (*from, *to) != (1, 4)
+            },
+        };
+        let (_, order) = dfs_finish_ord(&graph, &from_one, &mut no_shortcut, irrel);
+        assert_eq!(order.len(), 4);
+        assert!(order[&1] < order[&2] && order[&2] < order[&3] && order[&3] < order[&4]);
+        assert_eq!(looked_at, 4);
+
+        // Walking backwards from 4 reaches everything, and search sees node ids
+        let (found, order) = dfs_finish_ord(&graph, &from_four, Reverse, |&id: &usize| {
+            if id == 1 {
+                Some("one")
+            } else {
+                None
+            }
+        });
+        assert_eq!(found, Some("one"));
+        assert_eq!(order[&4], 0);
+    }
+
+    #[test]
+    fn test_mutations_keep_inverse_edges() {
+        // 1 -> 2 twice, 2 -> 3, 3 -> 1, 3 -> 4, 4 -> 4
+        let mut graph: Graph<usize> = Graph::new();
+        for (from, to) in [(1, 2), (1, 2), (2, 3), (3, 1), (3, 4), (4, 4)] {
+            graph.add_edge(from, to);
+        }
+        assert_eq!(graph.edge_count(), 6);
+        assert_eq!(graph.edge_multiplicity(&1, &2), 2);
+        assert_eq!(graph.edge_multiplicity(&2, &1), 0);
+
+        assert_eq!(graph.remove_edge(&1, &2), Some(()));
+        assert_eq!(graph.remove_edge(&2, &1), None);
+        assert_eq!(graph.edge_multiplicity(&1, &2), 1);
+        assert_eq!(graph.validate(), Ok(()));
+
+        let sub = graph.induced_subgraph(vec![1, 2, 3, 99]);
+        assert_eq!(sub.nodes.len(), 3);
+        assert_eq!(sub.edge_count(), 3);
+        assert_eq!(sub.validate(), Ok(()));
+
+        let mut reversed = sub.induced_subgraph(vec![1, 2, 3]);
+        reversed.reverse();
+        assert!(reversed.has_edge(&2, &1) && !reversed.has_edge(&1, &2));
+        assert_eq!(reversed.validate(), Ok(()));
+
+        // 3 swallows 4: 3 -> 4 goes away, 4's self loop becomes 3's
+        assert!(graph.contract_edge(&3, &4));
+        assert!(!graph.nodes.contains_key(&4));
+        assert_eq!(graph.edge_multiplicity(&3, &3), 1);
+        assert!(!graph.contract_edge(&1, &3));
+        // 1 swallows 2: 2 -> 3 is now 1 -> 3, next to 3 -> 1
+        assert!(graph.contract_edge(&1, &2));
+        assert!(graph.has_edge(&1, &3) && graph.has_edge(&3, &1));
+        assert_eq!(graph.validate(), Ok(()));
+
+        let removed = graph.remove_node(&3).unwrap();
+        assert_eq!(removed.id, 3);
+        assert_eq!(graph.nodes.len(), 1);
+        assert_eq!(graph.edge_count(), 0);
+        assert_eq!(graph.validate(), Ok(()));
+        assert!(graph.remove_node(&3).is_none());
+    }
+
+    #[test]
+    fn test_validate_finds_hand_edits() {
+        let mut graph: Graph<usize> = Graph::new();
+        graph.add_edge(1, 2);
+        graph.nodes.get_mut(&1).unwrap().edges.push(2);
+        assert_eq!(
+            graph.validate(),
+            Err(Inconsistency::MissingWeights { id: 1 })
+        );
+        graph.nodes.get_mut(&1).unwrap().weights.push(());
+        assert_eq!(
+            graph.validate(),
+            Err(Inconsistency::Unmirrored {
+                from: 1,
+                to: 2,
+                forward: 2,
+                inverse: 1
+            })
+        );
+
+        let mut nodes = HashMap::default();
+        nodes.insert(1, Node::new(1, vec![5], vec![]));
+        let e = Graph::try_from_nodes(nodes).unwrap_err();
+        assert_eq!(e, Inconsistency::MissingNode { from: 1, to: 5 });
+        assert_eq!(e.to_string(), "edge 1 -> 5 ends outside the graph");
+
+        let mut nodes = HashMap::default();
+        nodes.insert(1, Node::new(2, vec![], vec![]));
+        assert!(matches!(
+            Graph::try_from_nodes(nodes),
+            Err(Inconsistency::WrongKey { key: 1, id: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_weighted_edges() {
+        let mut graph: Graph<&str, u32> = Graph::new();
+        graph.add_weighted_edge("a", "b", 5);
+        graph.add_weighted_edge("a", "b", 7);
+        graph.add_weighted_edge("b", "c", 1);
+        graph.add_weighted_edge("c", "c", 2);
+        assert_eq!(graph.weight(&"a", &"b"), Some(&5));
+        assert_eq!(
+            graph.nodes["b"]
+                .weighted_inverse_edges()
+                .collect::<Vec<_>>(),
+            vec![(&"a", &5), (&"a", &7)]
+        );
+        assert_eq!(graph.remove_edge(&"a", &"b"), Some(5));
+        assert_eq!(graph.nodes["b"].inverse_weights, vec![7]);
+
+        // b swallows c: c's self loop stays a self loop, with its weight
+        assert!(graph.contract_edge(&"b", &"c"));
+        assert_eq!(graph.weight(&"b", &"b"), Some(&2));
+        graph.reverse();
+        assert_eq!(graph.weight(&"b", &"a"), Some(&7));
+        assert_eq!(graph.validate(), Ok(()));
+        let sub = graph.induced_subgraph(vec!["a", "b"]);
+        assert_eq!(sub.edge_count(), 2);
+        assert_eq!(sub.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_weighted_adjacency_matches_petgraph_loader() {
+        let text = "1\t2,3\t3,10\n2\t1,3\t3,4\n3\t1,10\t2,4\n4\n";
+        let mut graph: Graph<usize, i32> = Graph::new();
+        graph
+            .add_weighted_adjacency(weighted_lines(text, Mode::Strict))
+            .unwrap();
+        let (pet, _) = crate::aa_dijkstra::read_graph(weighted_lines(text, Mode::Strict)).unwrap();
+        assert_eq!(graph.nodes.len(), 4);
+        assert_eq!(graph.validate(), Ok(()));
+        assert_eq!(graph.weight(&3, &2), Some(&4));
+        assert_eq!(graph.edge_count(), pet.edge_count());
+        for edge in pet.raw_edges() {
+            // petgraph's nodes are 0-based in file order
+            let (from, to) = (edge.source().index() + 1, edge.target().index() + 1);
+            assert_eq!(graph.weight(&from, &to), Some(&edge.weight));
+        }
+    }
+
+    #[test]
+    fn test_hand_edited_weighted_file() {
+        // A trailing tab and a blank line are fine; "3" without its weight isn't
+        let text = "1\t2,3\t\n\n2\t1,3\t3\n3\t2,4\n";
+        let mut graph: Graph<usize, i32> = Graph::new();
+        let error = match graph.add_weighted_adjacency(weighted_lines(text, Mode::Strict)) {
+            Err(LoadError::Parse(e)) => e,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(
+            (error.line, error.column, error.token.as_str()),
+            (3, 7, "3")
+        );
+        assert_eq!(
+            error.to_string(),
+            "x:3:7: expected 'node,weight', found \"3\""
+        );
+
+        let mut graph: Graph<usize, i32> = Graph::new();
+        let warnings = graph
+            .add_weighted_adjacency(weighted_lines(text, Mode::Lenient))
+            .unwrap();
+        let (pet, pet_warnings) =
+            crate::aa_dijkstra::read_graph(weighted_lines(text, Mode::Lenient)).unwrap();
+        assert_eq!(warnings, vec![error.clone()]);
+        assert_eq!(pet_warnings, vec![error]);
+        // Line 3 is gone, node 2 along with it, except as the end of other edges
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(graph.weight(&3, &2), Some(&4));
+        assert_eq!(pet.edge_count(), 2);
+    }
 }
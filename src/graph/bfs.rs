@@ -0,0 +1,160 @@
+use super::scc::Components;
+use super::{Adjacency, Neighbours, Undirected};
+use fxhash::FxHashMap as HashMap;
+use std::hash::Hash;
+
+/// Hop counts from the nearest source. `layers[d]` holds the nodes at distance `d` in the order
+/// they were reached, and following `parent` from any reached node leads back to a source
+/// along a shortest path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bfs<Id: Eq + Hash> {
+    pub distance: HashMap<Id, usize>,
+    pub parent: HashMap<Id, Id>,
+    pub layers: Vec<Vec<Id>>,
+}
+
+impl<Id: Clone + Eq + Hash> Bfs<Id> {
+    pub fn reached(&self, id: &Id) -> bool {
+        self.distance.contains_key(id)
+    }
+
+    // Source first, `target` last; None if `target` wasn't reached
+    pub fn path_to(&self, target: &Id) -> Option<Vec<Id>> {
+        if !self.reached(target) {
+            return None;
+        }
+        let mut path = vec![target.clone()];
+        while let Some(parent) = self.parent.get(path.last().unwrap()) {
+            path.push(parent.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    pub fn eccentricity(&self) -> usize {
+        self.layers.len().saturating_sub(1)
+    }
+}
+
+// Layer by layer from all `sources` at once, so each node ends up at its distance from the
+// closest one. `neighbours` picks the direction: Forward, Reverse or Undirected.
+pub fn bfs<G, N>(
+    graph: &G,
+    sources: impl IntoIterator<Item = G::Id>,
+    mut neighbours: N,
+) -> Bfs<G::Id>
+where
+    G: Adjacency + ?Sized,
+    N: Neighbours<G>,
+{
+    let mut result = Bfs {
+        distance: HashMap::default(),
+        parent: HashMap::default(),
+        layers: Vec::new(),
+    };
+    let mut layer = Vec::new();
+    for source in sources {
+        if !result.distance.contains_key(&source) {
+            result.distance.insert(source.clone(), 0);
+            layer.push(source);
+        }
+    }
+    while !layer.is_empty() {
+        let depth = result.layers.len() + 1;
+        let mut next = Vec::new();
+        for node in &layer {
+            for to in neighbours.neighbours(graph, node) {
+                if !result.distance.contains_key(to) {
+                    result.distance.insert(to.clone(), depth);
+                    result.parent.insert(to.clone(), node.clone());
+                    next.push(to.clone());
+                }
+            }
+        }
+        result.layers.push(layer);
+        layer = next;
+    }
+    result
+}
+
+// Weakly connected components: edges count both ways. Numbered in the order node_ids() first
+// reaches them.
+pub fn connected_components<G: Adjacency + ?Sized>(graph: &G) -> Components<G::Id> {
+    let mut components = Components {
+        of: HashMap::default(),
+        sizes: Vec::new(),
+    };
+    for root in graph.node_ids() {
+        if components.of.contains_key(&root) {
+            continue;
+        }
+        let c = components.sizes.len();
+        let reached = bfs(graph, Some(root), Undirected);
+        components.sizes.push(reached.distance.len());
+        for (id, _) in reached.distance {
+            components.of.insert(id, c);
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::csr::CsrGraph;
+    use crate::graph::{Forward, Graph, Reverse};
+
+    #[test]
+    fn layers_and_paths() {
+        // 1 -> 2 -> 3 -> 4, 1 -> 5 -> 4, 6 -> 1, and 7 -> 8 off to the side
+        let mut graph: Graph<usize> = Graph::new();
+        for (from, to) in [(1, 2), (2, 3), (3, 4), (1, 5), (5, 4), (6, 1), (7, 8)] {
+            graph.add_edge(from, to);
+        }
+        let from_one = bfs(&graph, Some(1), Forward);
+        assert_eq!(from_one.layers.len(), 3);
+        assert_eq!(from_one.distance[&4], 2);
+        assert_eq!(from_one.path_to(&4), Some(vec![1, 5, 4]));
+        assert_eq!(from_one.path_to(&1), Some(vec![1]));
+        assert_eq!(from_one.path_to(&6), None);
+        assert_eq!(from_one.eccentricity(), 2);
+
+        let back = bfs(&graph, Some(4), Reverse);
+        assert_eq!(back.distance[&6], 3);
+        let mut layer: Vec<usize> = back.layers[1].clone();
+        layer.sort_unstable();
+        assert_eq!(layer, vec![3, 5]);
+
+        // Many sources: each node at its distance from the nearest
+        let both = bfs(&graph, vec![3, 6, 3], Forward);
+        assert_eq!(both.layers[0], vec![3, 6]);
+        assert_eq!((both.distance[&4], both.distance[&2]), (1, 2));
+        assert_eq!(both.path_to(&2), Some(vec![6, 1, 2]));
+
+        let components = connected_components(&graph);
+        assert_eq!(components.count(), 2);
+        assert_eq!(components.of[&6], components.of[&4]);
+        assert_ne!(components.of[&7], components.of[&1]);
+        let mut sizes = components.sizes.clone();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![2, 6]);
+    }
+
+    #[test]
+    fn hop_counts_agree_with_petgraph() {
+        let edges = crate::generate::scc_edges(300, &[50, 20], 900, 11);
+        let csr = CsrGraph::from_edges(None, edges.iter().copied());
+        let source = csr.index_of(&edges[0].0).unwrap();
+        let ours = bfs(&csr, Some(source), Forward);
+
+        let pet: petgraph::graphmap::DiGraphMap<usize, ()> = edges.iter().copied().collect();
+        let theirs = petgraph::algo::dijkstra(&pet, edges[0].0, None, |_| 1usize);
+        assert_eq!(ours.distance.len(), theirs.len());
+        for (id, hops) in theirs {
+            assert_eq!(ours.distance[&csr.index_of(&id).unwrap()], hops);
+        }
+        for (to, from) in &ours.parent {
+            assert_eq!(ours.distance[to], ours.distance[from] + 1);
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use super::Graph;
+use fxhash::FxHashMap as HashMap;
+use fxhash::FxHashSet as HashSet;
+use std::convert::TryFrom;
+use std::hash::Hash;
+
+/// An undirected multigraph on dense indices `0..ids.len()`, every edge with an id of its own.
+/// Low-link needs the ids to tell a parallel edge from the one it came in on: two edges
+/// between the same nodes are never bridges. Self loops can't matter for cuts, so they aren't
+/// edges here, only noted in `loops`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndirectedView<T> {
+    pub ids: Vec<T>,
+    // Endpoints of edge `e`
+    pub edges: Vec<(u32, u32)>,
+    // Nodes with a self loop, once each
+    pub loops: Vec<u32>,
+    // (neighbour, edge) pairs of each node
+    adjacency: Vec<Vec<(u32, u32)>>,
+}
+
+impl<T: Clone> UndirectedView<T> {
+    pub fn from_edges(ids: Vec<T>, edges: impl IntoIterator<Item = (usize, usize)>) -> Self {
+        let mut view = UndirectedView {
+            adjacency: vec![Vec::new(); ids.len()],
+            ids,
+            edges: Vec::new(),
+            loops: Vec::new(),
+        };
+        for (a, b) in edges {
+            let (a, b) = (a as u32, b as u32);
+            if a == b {
+                view.loops.push(a);
+                continue;
+            }
+            let e = u32::try_from(view.edges.len()).expect("more than u32::MAX edges");
+            view.edges.push((a, b));
+            view.adjacency[a as usize].push((b, e));
+            view.adjacency[b as usize].push((a, e));
+        }
+        view.loops.sort_unstable();
+        view.loops.dedup();
+        view
+    }
+
+    // Adjacency lists that name every edge at both ends, like kargerMinCut.txt: `lists[v]`
+    // holds the neighbours of `v`, parallel edges as often as there are of them.
+    pub fn from_symmetric_lists(ids: Vec<T>, lists: &[Vec<usize>]) -> Self {
+        // Each edge counted once, from its lower end
+        let edges = lists
+            .iter()
+            .enumerate()
+            .flat_map(|(v, list)| list.iter().filter(move |&&w| v <= w).map(move |&w| (v, w)));
+        let edges: Vec<(usize, usize)> = edges.collect();
+        UndirectedView::from_edges(ids, edges)
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn neighbours(&self, v: usize) -> &[(u32, u32)] {
+        &self.adjacency[v]
+    }
+
+    // Everything reachable from node 0; an empty view counts as connected
+    pub fn is_connected(&self) -> bool {
+        let n = self.node_count();
+        if n == 0 {
+            return true;
+        }
+        let mut seen = vec![false; n];
+        seen[0] = true;
+        let mut stack = vec![0];
+        let mut reached = 1;
+        while let Some(v) = stack.pop() {
+            for &(w, _) in self.neighbours(v) {
+                if !seen[w as usize] {
+                    seen[w as usize] = true;
+                    reached += 1;
+                    stack.push(w as usize);
+                }
+            }
+        }
+        reached == n
+    }
+}
+
+impl<T: Clone + Eq + Hash> UndirectedView<T> {
+    // The simple graph underneath: `u` and `v` are joined if there is an edge either way, once
+    // however many there are.
+    pub fn from_graph<W>(graph: &Graph<T, W>) -> Self {
+        let ids: Vec<T> = graph.nodes.keys().cloned().collect();
+        let index: HashMap<&T, usize> = ids.iter().enumerate().map(|(i, id)| (id, i)).collect();
+        let mut seen = HashSet::default();
+        let mut edges = Vec::new();
+        for node in graph.nodes.values() {
+            let a = index[&node.id];
+            for to in &node.edges {
+                let b = index[to];
+                if seen.insert((a.min(b), a.max(b))) {
+                    edges.push((a, b));
+                }
+            }
+        }
+        UndirectedView::from_edges(ids, edges)
+    }
+}
+
+/// Where the graph falls apart. Components are edge sets: every edge is in exactly one, a
+/// node is in several exactly when it's an articulation point, and a bridge is a component
+/// of its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Biconnectivity<T> {
+    // Nodes whose removal disconnects their connected component
+    pub articulation_points: Vec<T>,
+    // Edges whose removal does; a pair of parallel edges is never one
+    pub bridges: Vec<(T, T)>,
+    pub components: Vec<Vec<(T, T)>>,
+}
+
+impl<T: Clone + Eq + Hash> Biconnectivity<T> {
+    // The nodes of each component
+    pub fn component_nodes(&self) -> Vec<Vec<T>> {
+        self.components
+            .iter()
+            .map(|edges| {
+                let mut seen = HashSet::default();
+                let mut nodes = Vec::new();
+                for (a, b) in edges {
+                    for id in [a, b] {
+                        if seen.insert(id) {
+                            nodes.push(id.clone());
+                        }
+                    }
+                }
+                nodes
+            })
+            .collect()
+    }
+}
+
+// Hopcroft and Tarjan's low-link search with an explicit stack, so long paths are fine.
+// `low[v]` is the earliest discovery time reachable from v's subtree using at most one edge
+// that isn't a tree edge. A tree edge p - v with low[v] > disc[p] is a bridge; with
+// low[v] >= disc[p], p cuts v's subtree off (the root only if it has two such children).
+pub fn biconnectivity<T: Clone>(view: &UndirectedView<T>) -> Biconnectivity<T> {
+    const UNSEEN: usize = usize::MAX;
+    let n = view.node_count();
+    let mut disc = vec![UNSEEN; n];
+    let mut low = vec![0; n];
+    let mut cut = vec![false; n];
+    let mut time = 0;
+    let mut result = Biconnectivity {
+        articulation_points: Vec::new(),
+        bridges: Vec::new(),
+        components: Vec::new(),
+    };
+    let pair = |e: u32| {
+        let (a, b) = view.edges[e as usize];
+        (view.ids[a as usize].clone(), view.ids[b as usize].clone())
+    };
+    // Edges of components not closed yet
+    let mut edge_stack: Vec<u32> = Vec::new();
+    // (node, edge it was reached by, next neighbour to look at)
+    let mut work: Vec<(usize, Option<u32>, usize)> = Vec::new();
+
+    for root in 0..n {
+        if disc[root] != UNSEEN {
+            continue;
+        }
+        disc[root] = time;
+        low[root] = time;
+        time += 1;
+        let mut root_children = 0;
+        work.push((root, None, 0));
+        while let Some((v, via, next)) = work.last_mut() {
+            let v = *v;
+            if let Some(&(w, e)) = view.neighbours(v).get(*next) {
+                *next += 1;
+                let w = w as usize;
+                if Some(e) == *via {
+                    continue;
+                }
+                if disc[w] == UNSEEN {
+                    edge_stack.push(e);
+                    disc[w] = time;
+                    low[w] = time;
+                    time += 1;
+                    work.push((w, Some(e), 0));
+                } else if disc[w] < disc[v] {
+                    // Back to an ancestor, or a parallel edge to the parent
+                    edge_stack.push(e);
+                    low[v] = low[v].min(disc[w]);
+                }
+                // Otherwise w is a finished descendant and this edge was pushed from its side
+                continue;
+            }
+            let via = work.pop().unwrap().1;
+            let p = match work.last() {
+                Some(&(p, _, _)) => p,
+                None => break,
+            };
+            let via = via.unwrap();
+            low[p] = low[p].min(low[v]);
+            if low[v] > disc[p] {
+                result.bridges.push(pair(via));
+            }
+            if low[v] >= disc[p] {
+                if p == root {
+                    root_children += 1;
+                } else {
+                    cut[p] = true;
+                }
+                let mut component = Vec::new();
+                while let Some(e) = edge_stack.pop() {
+                    component.push(pair(e));
+                    if e == via {
+                        break;
+                    }
+                }
+                result.components.push(component);
+            }
+        }
+        cut[root] = root_children >= 2;
+    }
+    result.articulation_points = (0..n)
+        .filter(|&v| cut[v])
+        .map(|v| view.ids[v].clone())
+        .collect();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::bfs::{bfs, connected_components};
+    use crate::graph::Undirected;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn undirected(edges: &[(u32, u32)]) -> Graph<u32> {
+        let mut graph = Graph::new();
+        for &(a, b) in edges {
+            graph.add_edge(a, b);
+            graph.add_edge(b, a);
+        }
+        graph
+    }
+
+    fn sorted<T: Ord + Clone>(items: &[T]) -> Vec<T> {
+        let mut items = items.to_vec();
+        items.sort_unstable();
+        items
+    }
+
+    #[test]
+    fn two_triangles_and_a_tail() {
+        // Triangles 1-2-3 and 4-5-6 joined by the bridge 3 - 4, a tail 6 - 7, and 8 alone
+        let mut graph = undirected(&[
+            (1, 2),
+            (2, 3),
+            (3, 1),
+            (3, 4),
+            (4, 5),
+            (5, 6),
+            (6, 4),
+            (6, 7),
+        ]);
+        graph.add_node(&8);
+        let result = biconnectivity(&UndirectedView::from_graph(&graph));
+        assert_eq!(sorted(&result.articulation_points), vec![3, 4, 6]);
+        let bridges: Vec<(u32, u32)> = result
+            .bridges
+            .iter()
+            .map(|&(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        assert_eq!(sorted(&bridges), vec![(3, 4), (6, 7)]);
+        let mut sizes: Vec<usize> = result.components.iter().map(Vec::len).collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![1, 1, 3, 3]);
+        let mut nodes: Vec<Vec<u32>> = result.component_nodes().iter().map(|c| sorted(c)).collect();
+        nodes.sort();
+        assert_eq!(
+            nodes,
+            vec![vec![1, 2, 3], vec![3, 4], vec![4, 5, 6], vec![6, 7]]
+        );
+    }
+
+    #[test]
+    fn parallel_edges_are_not_bridges() {
+        // 0 = 1 - 2, as adjacency lists naming each edge at both ends
+        let lists = vec![vec![1, 1], vec![0, 0, 2], vec![1]];
+        let view = UndirectedView::from_symmetric_lists(vec!['a', 'b', 'c'], &lists);
+        assert_eq!(view.edges.len(), 3);
+        let result = biconnectivity(&view);
+        assert_eq!(result.bridges, vec![('b', 'c')]);
+        assert_eq!(result.articulation_points, vec!['b']);
+        assert_eq!(result.components.len(), 2);
+    }
+
+    #[test]
+    fn bridges_agree_with_brute_force() {
+        let mut rng = StdRng::seed_from_u64(20);
+        for _ in 0..50 {
+            let n = rng.gen_range(2..12u32);
+            let m = rng.gen_range(0..2 * n);
+            let edges: Vec<(u32, u32)> = (0..m)
+                .map(|_| (rng.gen_range(0..n), rng.gen_range(0..n)))
+                .filter(|(a, b)| a != b)
+                .collect();
+            let mut graph = undirected(&edges);
+            for v in 0..n {
+                graph.add_node(&v);
+            }
+            let result = biconnectivity(&UndirectedView::from_graph(&graph));
+            let mut simple: Vec<(u32, u32)> =
+                edges.iter().map(|&(a, b)| (a.min(b), a.max(b))).collect();
+            simple.sort_unstable();
+            simple.dedup();
+
+            // A bridge: without it, its ends aren't connected any more
+            let bridges: HashSet<(u32, u32)> = result
+                .bridges
+                .iter()
+                .map(|&(a, b)| (a.min(b), a.max(b)))
+                .collect();
+            for &(a, b) in &simple {
+                let mut without = graph.induced_subgraph(0..n);
+                // Every copy of it, in both directions
+                while without.remove_edge(&a, &b).is_some() {}
+                while without.remove_edge(&b, &a).is_some() {}
+                let split = !bfs(&without, Some(a), Undirected).reached(&b);
+                assert_eq!(
+                    bridges.contains(&(a, b)),
+                    split,
+                    "{:?} in {:?}",
+                    (a, b),
+                    simple
+                );
+            }
+
+            // An articulation point: without it, its neighbours aren't all connected
+            let cuts: HashSet<u32> = result.articulation_points.iter().copied().collect();
+            for v in 0..n {
+                let without = graph.induced_subgraph((0..n).filter(|&w| w != v));
+                let components = connected_components(&without);
+                let around: HashSet<usize> = graph.nodes[&v]
+                    .edges
+                    .iter()
+                    .map(|w| components.of[w])
+                    .collect();
+                assert_eq!(cuts.contains(&v), around.len() > 1, "{} in {:?}", v, simple);
+            }
+            assert_eq!(
+                result.components.iter().map(Vec::len).sum::<usize>(),
+                simple.len()
+            );
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use super::biconnected::UndirectedView;
+use super::Graph;
+use std::fmt;
+use std::hash::Hash;
+
+/// A two-colouring: every edge has one end in `left` and the other in `right`. The first
+/// node of each connected component is on the left, and so are isolated nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bipartition<T> {
+    pub left: Vec<T>,
+    pub right: Vec<T>,
+}
+
+/// Proof that there's no two-colouring: an odd number of nodes, each joined to the next and
+/// the last to the first. A self loop is a cycle of one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OddCycle<T> {
+    pub nodes: Vec<T>,
+}
+
+impl<T: fmt::Debug> fmt::Display for OddCycle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "odd cycle:")?;
+        for node in self.nodes.iter().chain(self.nodes.first()) {
+            write!(f, " {:?}", node)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for OddCycle<T> {}
+
+// Breadth first, colouring by the parity of the depth. An edge between two nodes of one
+// colour joins two nodes of the same depth, and their paths up the BFS tree meet somewhere:
+// the two paths and the edge make a cycle of 2 * (depth - meeting depth) + 1 nodes.
+pub fn two_colour<T: Clone>(view: &UndirectedView<T>) -> Result<Bipartition<T>, OddCycle<T>> {
+    if let Some(&v) = view.loops.first() {
+        return Err(OddCycle {
+            nodes: vec![view.ids[v as usize].clone()],
+        });
+    }
+    let n = view.node_count();
+    let mut depth = vec![usize::MAX; n];
+    let mut parent = vec![usize::MAX; n];
+    let mut queue = std::collections::VecDeque::new();
+    for root in 0..n {
+        if depth[root] != usize::MAX {
+            continue;
+        }
+        depth[root] = 0;
+        queue.push_back(root);
+        while let Some(v) = queue.pop_front() {
+            for &(w, _) in view.neighbours(v) {
+                let w = w as usize;
+                if depth[w] == usize::MAX {
+                    depth[w] = depth[v] + 1;
+                    parent[w] = v;
+                    queue.push_back(w);
+                } else if depth[w] % 2 == depth[v] % 2 {
+                    return Err(odd_cycle(view, &parent, v, w));
+                }
+            }
+        }
+    }
+    let mut result = Bipartition {
+        left: Vec::new(),
+        right: Vec::new(),
+    };
+    for (v, d) in depth.into_iter().enumerate() {
+        let id = view.ids[v].clone();
+        if d % 2 == 0 {
+            result.left.push(id);
+        } else {
+            result.right.push(id);
+        }
+    }
+    Ok(result)
+}
+
+// `a` and `b` are joined and equally deep: climb from both until they meet
+fn odd_cycle<T: Clone>(
+    view: &UndirectedView<T>,
+    parent: &[usize],
+    a: usize,
+    b: usize,
+) -> OddCycle<T> {
+    let (mut up, mut down) = (vec![a], vec![b]);
+    let (mut x, mut y) = (a, b);
+    while x != y {
+        x = parent[x];
+        y = parent[y];
+        up.push(x);
+        down.push(y);
+    }
+    // a .. meeting point, then back down to b; the edge b - a closes it
+    down.pop();
+    up.extend(down.into_iter().rev());
+    OddCycle {
+        nodes: up.into_iter().map(|v| view.ids[v].clone()).collect(),
+    }
+}
+
+// Edges count both ways, whichever way round they were added
+pub fn bipartition<T: Clone + Eq + Hash, W>(
+    graph: &Graph<T, W>,
+) -> Result<Bipartition<T>, OddCycle<T>> {
+    two_colour(&UndirectedView::from_graph(graph))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn graph(edges: &[(u32, u32)]) -> Graph<u32> {
+        let mut graph = Graph::new();
+        for &(a, b) in edges {
+            graph.add_edge(a, b);
+        }
+        graph
+    }
+
+    // The cycle is odd, and really is one in `graph`
+    fn check_cycle(graph: &Graph<u32>, cycle: &OddCycle<u32>) {
+        assert_eq!(cycle.nodes.len() % 2, 1, "{}", cycle);
+        for (i, a) in cycle.nodes.iter().enumerate() {
+            let b = &cycle.nodes[(i + 1) % cycle.nodes.len()];
+            assert!(graph.has_edge(a, b) || graph.has_edge(b, a), "{}", cycle);
+        }
+    }
+
+    #[test]
+    fn even_and_odd_cycles() {
+        // A square with a tail, edges going whichever way: bipartite
+        let square = graph(&[(1, 2), (3, 2), (3, 4), (4, 1), (4, 5)]);
+        let mut colours = bipartition(&square).unwrap();
+        colours.left.sort_unstable();
+        colours.right.sort_unstable();
+        assert!(
+            (colours.left == vec![1, 3, 5] && colours.right == vec![2, 4])
+                || (colours.left == vec![2, 4] && colours.right == vec![1, 3, 5])
+        );
+
+        // A pentagon hanging off a path
+        let pentagon = graph(&[(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (6, 2)]);
+        let cycle = bipartition(&pentagon).unwrap_err();
+        assert_eq!(cycle.nodes.len(), 5);
+        check_cycle(&pentagon, &cycle);
+
+        let looped = graph(&[(1, 2), (2, 2)]);
+        let cycle = bipartition(&looped).unwrap_err();
+        assert_eq!(cycle.nodes, vec![2]);
+        assert_eq!(cycle.to_string(), "odd cycle: 2 2");
+    }
+
+    #[test]
+    fn random_graphs() {
+        let mut rng = StdRng::seed_from_u64(23);
+        for _ in 0..200 {
+            let n = rng.gen_range(1..15u32);
+            let m = rng.gen_range(0..2 * n);
+            let mut g = graph(&[]);
+            for v in 0..n {
+                g.add_node(&v);
+            }
+            for _ in 0..m {
+                let (a, b) = (rng.gen_range(0..n), rng.gen_range(0..n));
+                if a != b {
+                    g.add_edge(a, b);
+                }
+            }
+            match bipartition(&g) {
+                Ok(colours) => {
+                    assert_eq!(colours.left.len() + colours.right.len(), n as usize);
+                    for node in g.nodes.values() {
+                        let side = colours.left.contains(&node.id);
+                        for to in &node.edges {
+                            assert_ne!(colours.left.contains(to), side);
+                        }
+                    }
+                }
+                Err(cycle) => check_cycle(&g, &cycle),
+            }
+        }
+    }
+}
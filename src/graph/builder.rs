@@ -0,0 +1,177 @@
+// Small graphs written out in one line, mostly for tests:
+//
+//     let graph: Graph<u32> = crate::graph! { 1 -> 2, 3; 3 -> 4 [w = 5]; 2 -- 4; 5 };
+//
+// `a -> b` is an edge from a to b, `a -- b` one each way with the same weight, and a node
+// on its own is just a node. Several targets share a source: `1 -> 2, 3`. Edges without a
+// `[w = ...]` get the weight type's default. Ids are single tokens: literals, variables,
+// or anything in parentheses, like `(-1)`.
+//
+// The macro fills in a `Builder` and converts it into whatever the annotation asks for:
+// `graph::Graph`, a petgraph `Graph`, the Karger graph, or the `Builder` itself to convert
+// more than once.
+
+use super::Graph;
+use fxhash::FxHashMap as HashMap;
+use std::hash::Hash;
+
+/// Nodes in the order they first came up, and directed edges in the order they were added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Builder<T, W = ()> {
+    pub nodes: Vec<T>,
+    pub edges: Vec<(T, T, W)>,
+}
+
+impl<T: Clone + PartialEq, W: Clone> Builder<T, W> {
+    pub fn new() -> Self {
+        Builder {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    // Fixtures are small, so a linear search keeps `T` down to `PartialEq`
+    pub fn node(&mut self, id: T) {
+        if !self.nodes.contains(&id) {
+            self.nodes.push(id);
+        }
+    }
+
+    pub fn edge(&mut self, from: T, to: T, weight: W) {
+        self.node(from.clone());
+        self.node(to.clone());
+        self.edges.push((from, to, weight));
+    }
+
+    // A self loop is still only one edge
+    pub fn undirected_edge(&mut self, a: T, b: T, weight: W) {
+        self.edge(a.clone(), b.clone(), weight.clone());
+        if a != b {
+            self.edge(b, a, weight);
+        }
+    }
+}
+
+impl<T: Clone + PartialEq, W: Clone> Default for Builder<T, W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + Eq + Hash, W: Clone> From<Builder<T, W>> for Graph<T, W> {
+    fn from(builder: Builder<T, W>) -> Self {
+        let mut graph = Graph::new();
+        for id in &builder.nodes {
+            graph.add_node(id);
+        }
+        for (from, to, weight) in builder.edges {
+            graph.add_weighted_edge(from, to, weight);
+        }
+        graph
+    }
+}
+
+// Node weights are the ids, like `convert::to_petgraph`, and the k-th node to come up is
+// `NodeIndex::new(k)`
+impl<T: Clone + Eq + Hash, W: Clone> From<Builder<T, W>> for petgraph::Graph<T, W> {
+    fn from(builder: Builder<T, W>) -> Self {
+        let mut pet = petgraph::Graph::with_capacity(builder.nodes.len(), builder.edges.len());
+        let mut index = HashMap::default();
+        for id in builder.nodes {
+            index.insert(id.clone(), pet.add_node(id));
+        }
+        for (from, to, weight) in builder.edges {
+            pet.add_edge(index[&from], index[&to], weight);
+        }
+        pet
+    }
+}
+
+/// `graph! { 1 -> 2, 3; 3 -- 4 [w = 5] }`: see the top of this file for the syntax.
+#[macro_export]
+macro_rules! graph {
+    (@statements $b:ident;) => {};
+    (@statements $b:ident; ; $($rest:tt)*) => {
+        $crate::graph!(@statements $b; $($rest)*)
+    };
+    (@statements $b:ident; $from:tt -> $($rest:tt)*) => {
+        $b.node($from);
+        $crate::graph!(@targets $b, $from, edge; $($rest)*)
+    };
+    (@statements $b:ident; $from:tt -- $($rest:tt)*) => {
+        $b.node($from);
+        $crate::graph!(@targets $b, $from, undirected_edge; $($rest)*)
+    };
+    (@statements $b:ident; $node:tt $($rest:tt)*) => {
+        $b.node($node);
+        $crate::graph!(@end $b; $($rest)*)
+    };
+    (@targets $b:ident, $from:tt, $add:ident; $to:tt [w = $w:expr] $($rest:tt)*) => {
+        $b.$add($from, $to, $w);
+        $crate::graph!(@next $b, $from, $add; $($rest)*)
+    };
+    (@targets $b:ident, $from:tt, $add:ident; $to:tt $($rest:tt)*) => {
+        $b.$add($from, $to, Default::default());
+        $crate::graph!(@next $b, $from, $add; $($rest)*)
+    };
+    (@next $b:ident, $from:tt, $add:ident; , $($rest:tt)*) => {
+        $crate::graph!(@targets $b, $from, $add; $($rest)*)
+    };
+    (@next $b:ident, $from:tt, $add:ident; $($rest:tt)*) => {
+        $crate::graph!(@end $b; $($rest)*)
+    };
+    (@end $b:ident;) => {};
+    (@end $b:ident; ; $($rest:tt)*) => {
+        $crate::graph!(@statements $b; $($rest)*)
+    };
+    ($($body:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut builder = $crate::graph::builder::Builder::new();
+        $crate::graph!(@statements builder; $($body)*);
+        builder.into()
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::NodeIndex;
+
+    #[test]
+    fn one_description_several_graphs() {
+        let builder: Builder<u32, i32> =
+            crate::graph! { 1 -> 2, 3; 3 -> 4 [w = 5]; 2 -- 4 [w = 2]; 6; };
+        assert_eq!(builder.nodes, vec![1, 2, 3, 4, 6]);
+        assert_eq!(
+            builder.edges,
+            vec![(1, 2, 0), (1, 3, 0), (3, 4, 5), (2, 4, 2), (4, 2, 2)]
+        );
+
+        let graph: Graph<u32, i32> = builder.clone().into();
+        assert_eq!(graph.nodes.len(), 5);
+        assert_eq!(graph.edge_count(), 5);
+        assert_eq!(graph.weight(&3, &4), Some(&5));
+        assert_eq!(graph.weight(&4, &2), Some(&2));
+        assert!(graph.nodes[&6].edges.is_empty());
+        assert_eq!(graph.validate(), Ok(()));
+
+        let pet: petgraph::Graph<u32, i32> = builder.into();
+        assert_eq!(pet[NodeIndex::new(3)], 4);
+        let edge = pet.find_edge(NodeIndex::new(2), NodeIndex::new(3)).unwrap();
+        assert_eq!(pet[edge], 5);
+        assert_eq!(pet.edge_count(), 5);
+    }
+
+    #[test]
+    fn ids_and_loops() {
+        // Unweighted, straight into a graph; strings and negative numbers work too
+        let graph: Graph<&str> = crate::graph! { "a" -- "a", "b"; "b" -> "c" };
+        assert_eq!(graph.edge_count(), 4);
+        assert!(graph.has_edge(&"a", &"a") && graph.has_edge(&"b", &"a"));
+        let x = -1;
+        let graph: Graph<i64> = crate::graph! { x -> (-2); (-2) -> 3 };
+        assert!(graph.has_edge(&-1, &-2) && graph.has_edge(&-2, &3));
+        let empty: Graph<u8> = crate::graph! {};
+        assert!(empty.nodes.is_empty());
+    }
+}
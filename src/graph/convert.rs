@@ -0,0 +1,216 @@
+// Conversions to and from petgraph, so its algorithms can check ours and run on our data.
+// petgraph nodes carry our ids as their weights, and edges carry our payloads.
+
+use super::Graph;
+use fxhash::FxHashMap as HashMap;
+use petgraph::graph::{IndexType, NodeIndex};
+use petgraph::EdgeType;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::Hash;
+
+// A petgraph graph with the same nodes and edges, and where each id ended up. Nodes are
+// added in map order, each node's edges in list order.
+pub fn to_petgraph<T, W>(graph: &Graph<T, W>) -> (petgraph::Graph<T, W>, HashMap<T, NodeIndex>)
+where
+    T: Clone + Eq + Hash,
+    W: Clone,
+{
+    let mut pet = petgraph::Graph::with_capacity(graph.nodes.len(), graph.edge_count());
+    let mut index = HashMap::default();
+    for id in graph.nodes.keys() {
+        index.insert(id.clone(), pet.add_node(id.clone()));
+    }
+    for node in graph.nodes.values() {
+        for (to, weight) in node.weighted_edges() {
+            pet.add_edge(index[&node.id], index[to], weight.clone());
+        }
+    }
+    (pet, index)
+}
+
+// Node weights are the ids; `to_petgraph` also says which index each one got.
+impl<T, W> From<&Graph<T, W>> for petgraph::Graph<T, W>
+where
+    T: Clone + Eq + Hash,
+    W: Clone,
+{
+    fn from(graph: &Graph<T, W>) -> Self {
+        to_petgraph(graph).0
+    }
+}
+
+/// Two petgraph nodes carry the same id, so they can't both be nodes of a `Graph`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateId<T> {
+    pub id: T,
+    pub first: usize,
+    pub second: usize,
+}
+
+impl<T: fmt::Debug> fmt::Display for DuplicateId<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "petgraph nodes {} and {} both have id {:?}",
+            self.first, self.second, self.id
+        )
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for DuplicateId<T> {}
+
+// Node weights become the ids, edge weights the payloads. An undirected petgraph edge turns
+// into one edge each way.
+impl<T, W, Ty, Ix> TryFrom<&petgraph::Graph<T, W, Ty, Ix>> for Graph<T, W>
+where
+    T: Clone + Eq + Hash,
+    W: Clone,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    type Error = DuplicateId<T>;
+
+    fn try_from(pet: &petgraph::Graph<T, W, Ty, Ix>) -> Result<Self, Self::Error> {
+        let mut graph = Graph::new();
+        let mut first: HashMap<&T, usize> = HashMap::default();
+        for i in pet.node_indices() {
+            let id = &pet[i];
+            if let Some(&first) = first.get(id) {
+                return Err(DuplicateId {
+                    id: id.clone(),
+                    first,
+                    second: i.index(),
+                });
+            }
+            first.insert(id, i.index());
+            graph.add_node(id);
+        }
+        for edge in pet.raw_edges() {
+            let (from, to) = (&pet[edge.source()], &pet[edge.target()]);
+            graph.add_weighted_edge(from.clone(), to.clone(), edge.weight.clone());
+            if !pet.is_directed() && from != to {
+                graph.add_weighted_edge(to.clone(), from.clone(), edge.weight.clone());
+            }
+        }
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::bfs::bfs;
+    use crate::graph::formats;
+    use crate::graph::parse::{Mode, Records};
+    use crate::graph::scc::{same_partition, tarjan, Components};
+    use crate::graph::Forward;
+
+    fn sample() -> Graph<&'static str, u32> {
+        let mut graph = Graph::new();
+        for (from, to, w) in [
+            ("a", "b", 4),
+            ("b", "c", 1),
+            ("a", "c", 7),
+            ("c", "a", 2),
+            ("c", "d", 3),
+            ("a", "b", 9),
+        ] {
+            graph.add_weighted_edge(from, to, w);
+        }
+        graph.add_node(&"lonely");
+        graph
+    }
+
+    #[test]
+    fn round_trips_keep_ids_and_weights() {
+        let graph = sample();
+        let (pet, index) = to_petgraph(&graph);
+        assert_eq!(pet.node_count(), 5);
+        assert_eq!(pet.edge_count(), 6);
+        assert_eq!(pet[index[&"d"]], "d");
+        let mut ab: Vec<u32> = pet
+            .edges_connecting(index[&"a"], index[&"b"])
+            .map(|e| *e.weight())
+            .collect();
+        // petgraph lists parallel edges newest first
+        ab.sort_unstable();
+        assert_eq!(ab, vec![4, 9]);
+
+        let back = Graph::try_from(&pet).unwrap();
+        assert_eq!(back.validate(), Ok(()));
+        for node in graph.nodes.values() {
+            let mut ours: Vec<_> = node.weighted_edges().collect();
+            let mut theirs: Vec<_> = back.nodes[&node.id].weighted_edges().collect();
+            ours.sort_unstable();
+            theirs.sort_unstable();
+            assert_eq!(ours, theirs);
+        }
+        assert_eq!(petgraph::Graph::from(&back).edge_count(), 6);
+    }
+
+    #[test]
+    fn undirected_and_duplicate_ids() {
+        let mut un: petgraph::graph::UnGraph<i32, i32> = petgraph::graph::UnGraph::new_undirected();
+        let (x, y) = (un.add_node(10), un.add_node(20));
+        un.add_edge(x, y, 5);
+        let graph = Graph::try_from(&un).unwrap();
+        assert_eq!(graph.weight(&20, &10), Some(&5));
+        assert_eq!(graph.edge_count(), 2);
+
+        un.add_node(10);
+        let e = Graph::try_from(&un).unwrap_err();
+        assert_eq!(
+            e,
+            DuplicateId {
+                id: 10,
+                first: 0,
+                second: 2
+            }
+        );
+        assert_eq!(e.to_string(), "petgraph nodes 0 and 2 both have id 10");
+    }
+
+    #[test]
+    fn petgraph_as_an_oracle() {
+        // The dijkstra loader's graph, in here and back, gives the same distances
+        let text = "1\t2,3\t3,10\n2\t1,3\t3,4\n3\t1,10\t2,4\n";
+        let lines = Records::new(
+            std::path::Path::new("x"),
+            text.as_bytes(),
+            Mode::Strict,
+            formats::weighted_adjacency_line,
+        );
+        let (pet, _) = crate::aa_dijkstra::read_graph(lines).unwrap();
+        let graph: Graph<i32, i32> = Graph::try_from(&pet).unwrap();
+        let (again, index) = to_petgraph(&graph);
+        let distances = petgraph::algo::dijkstra(&again, index[&1], None, |e| *e.weight());
+        assert_eq!(distances[&index[&3]], 7);
+
+        // SCCs and hop counts of a generated graph agree with petgraph's
+        let mut graph: Graph<usize> = Graph::new();
+        for (from, to) in crate::generate::scc_edges(200, &[40, 15, 3], 700, 3) {
+            graph.add_edge(from, to);
+        }
+        let (pet, index) = to_petgraph(&graph);
+        let mut theirs = Components {
+            of: HashMap::default(),
+            sizes: Vec::new(),
+        };
+        for scc in petgraph::algo::kosaraju_scc(&pet) {
+            theirs.sizes.push(scc.len());
+            for i in scc {
+                theirs.of.insert(pet[i], theirs.sizes.len() - 1);
+            }
+        }
+        assert!(same_partition(&tarjan(&graph), &theirs));
+
+        let source = *graph.nodes.keys().min().unwrap();
+        let hops = petgraph::algo::dijkstra(&pet, index[&source], None, |_| 1usize);
+        let ours = bfs(&graph, Some(source), Forward);
+        assert_eq!(hops.len(), ours.distance.len());
+        for (i, d) in hops {
+            assert_eq!(ours.distance[&pet[i]], d);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use super::parse::{LoadError, Mode, ParseError};
+use super::{Adjacency, Graph};
+use fxhash::FxHashMap as HashMap;
+use std::convert::TryFrom;
+use std::hash::Hash;
+use std::str::FromStr;
+
+/// Immutable compressed-sparse-row graph. Node ids are interned to dense `u32` indices in the
+/// order they first show up; the out-neighbours of node `i` are
+/// `out_targets[out_offsets[i]..out_offsets[i + 1]]`, and likewise for in-neighbours.
+///
+/// On SCC.txt this is a few flat arrays instead of 875,714 hash map entries with two `Vec`s
+/// each, and traversals index into slices instead of hashing ids.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsrGraph<T: Clone + Eq + Hash> {
+    ids: Vec<T>,
+    index: HashMap<T, u32>,
+    out_offsets: Vec<usize>,
+    out_targets: Vec<u32>,
+    in_offsets: Vec<usize>,
+    in_sources: Vec<u32>,
+}
+
+impl<T: Clone + Eq + Hash> CsrGraph<T> {
+    // `nodes` first, so that isolated nodes make it in too and get the first indices.
+    pub fn from_edges(
+        nodes: impl IntoIterator<Item = T>,
+        edges: impl IntoIterator<Item = (T, T)>,
+    ) -> Self {
+        let mut ids = Vec::new();
+        let mut index: HashMap<T, u32> = HashMap::default();
+        let mut intern = |id: T| -> u32 {
+            *index.entry(id).or_insert_with_key(|id| {
+                ids.push(id.clone());
+                u32::try_from(ids.len() - 1).expect("more than u32::MAX nodes")
+            })
+        };
+        for id in nodes {
+            intern(id);
+        }
+        let pairs: Vec<(u32, u32)> = edges
+            .into_iter()
+            .map(|(from, to)| (intern(from), intern(to)))
+            .collect();
+        let n = ids.len();
+        let (out_offsets, out_targets) = compress(n, pairs.iter().copied());
+        let (in_offsets, in_sources) = compress(n, pairs.iter().map(|&(from, to)| (to, from)));
+        CsrGraph {
+            ids,
+            index,
+            out_offsets,
+            out_targets,
+            in_offsets,
+            in_sources,
+        }
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.out_targets.len()
+    }
+
+    // Original id of dense index `i`
+    pub fn id(&self, i: u32) -> &T {
+        &self.ids[i as usize]
+    }
+
+    pub fn index_of(&self, id: &T) -> Option<u32> {
+        self.index.get(id).copied()
+    }
+
+    // Back to the hash map representation, e.g. to add or remove edges
+    pub fn to_graph(&self) -> Graph<T> {
+        let mut graph = Graph::new();
+        for id in &self.ids {
+            graph.add_node(id);
+        }
+        for (from, id) in self.ids.iter().enumerate() {
+            for &to in self.out_edges(&(from as u32)) {
+                graph.add_edge(id.clone(), self.ids[to as usize].clone());
+            }
+        }
+        graph
+    }
+}
+
+impl<T: FromStr + Clone + Eq + Hash> CsrGraph<T> {
+    // The graph, and the lines that were skipped if `mode` is lenient
+    pub fn load_from_directed_edges_file(
+        path: impl AsRef<std::path::Path>,
+        mode: Mode,
+    ) -> Result<(Self, Vec<ParseError>), LoadError> {
+        let mut edges = super::read_directed_edges(path, mode)?;
+        let graph = Self::from_edges(None, &mut edges);
+        Ok((graph, edges.finish()?))
+    }
+}
+
+// Counting sort of (from, to) pairs by `from` into offsets and targets, keeping the order
+// edges came in within each node.
+fn compress(n: usize, pairs: impl Iterator<Item = (u32, u32)> + Clone) -> (Vec<usize>, Vec<u32>) {
+    let mut offsets = vec![0usize; n + 1];
+    for (from, _) in pairs.clone() {
+        offsets[from as usize + 1] += 1;
+    }
+    for i in 0..n {
+        offsets[i + 1] += offsets[i];
+    }
+    let mut next = offsets.clone();
+    let mut targets = vec![0u32; offsets[n]];
+    for (from, to) in pairs {
+        targets[next[from as usize]] = to;
+        next[from as usize] += 1;
+    }
+    (offsets, targets)
+}
+
+// Payloads don't come along, the CSR arrays only know where edges go.
+impl<T: Clone + Eq + Hash, W> From<&Graph<T, W>> for CsrGraph<T> {
+    fn from(graph: &Graph<T, W>) -> Self {
+        CsrGraph::from_edges(
+            graph.nodes.keys().cloned(),
+            graph.nodes.values().flat_map(|node| {
+                node.edges
+                    .iter()
+                    .map(move |to| (node.id.clone(), to.clone()))
+            }),
+        )
+    }
+}
+
+impl<T: Clone + Eq + Hash> From<&CsrGraph<T>> for Graph<T> {
+    fn from(graph: &CsrGraph<T>) -> Self {
+        graph.to_graph()
+    }
+}
+
+impl<T: Clone + Eq + Hash> Adjacency for CsrGraph<T> {
+    type Id = u32;
+    fn node_count(&self) -> usize {
+        self.ids.len()
+    }
+    fn node_ids(&self) -> Vec<u32> {
+        (0..self.ids.len() as u32).collect()
+    }
+    fn out_edges(&self, &i: &u32) -> &[u32] {
+        &self.out_targets[self.out_offsets[i as usize]..self.out_offsets[i as usize + 1]]
+    }
+    fn in_edges(&self, &i: &u32) -> &[u32] {
+        &self.in_sources[self.in_offsets[i as usize]..self.in_offsets[i as usize + 1]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aa_kosajaru::kosaraju;
+
+    fn scc_sizes<G: Adjacency>(graph: &G) -> Vec<usize> {
+        let mut sizes: Vec<usize> = kosaraju(graph).iter().map(|c| c.nodes.len()).collect();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes
+    }
+
+    #[test]
+    fn offsets_and_round_trip() {
+        let mut graph: Graph<&str> = Graph::new();
+        graph.add_edge("a", "b");
+        graph.add_edge("a", "c");
+        graph.add_edge("c", "a");
+        graph.add_node(&"lonely");
+        let csr = CsrGraph::from(&graph);
+        assert_eq!(csr.node_count(), 4);
+        assert_eq!(csr.edge_count(), 3);
+        let a = csr.index_of(&"a").unwrap();
+        let mut out: Vec<&str> = csr.out_edges(&a).iter().map(|&i| *csr.id(i)).collect();
+        out.sort_unstable();
+        assert_eq!(out, vec!["b", "c"]);
+        let c = csr.index_of(&"c").unwrap();
+        assert_eq!(csr.in_edges(&a), &[c]);
+        assert!(csr.out_edges(&csr.index_of(&"lonely").unwrap()).is_empty());
+        assert_eq!(Graph::from(&csr), graph);
+    }
+
+    #[test]
+    fn kosaraju_agrees_with_hash_graph() {
+        let edges = crate::generate::scc_edges(400, &[100, 30, 3], 1500, 5);
+        let csr = CsrGraph::from_edges(None, edges.iter().copied());
+        let mut graph: Graph<usize> = Graph::new();
+        for &(from, to) in &edges {
+            graph.add_edge(from, to);
+        }
+        assert_eq!(csr.node_count(), 400);
+        assert_eq!(csr.edge_count(), 1500);
+        assert_eq!(scc_sizes(&csr), scc_sizes(&graph));
+        assert_eq!(&scc_sizes(&csr)[..4], &[100, 30, 3, 1]);
+    }
+}
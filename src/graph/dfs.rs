@@ -0,0 +1,300 @@
+use super::{Adjacency, Neighbours};
+use fxhash::FxHashMap as HashMap;
+
+/// What a visitor wants the search to do next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    // From `discover`: don't look at this node's edges. From `tree_edge`: don't go down this
+    // edge, the target stays undiscovered. Anywhere else the same as Continue.
+    Skip,
+    // Stop the whole search right away
+    Stop,
+}
+
+/// Callbacks for `dfs_visit`, all defaulting to doing nothing. Times come from one counter
+/// that ticks at every discovery and every finish, so a node's `[discover, finish]` interval
+/// contains exactly the intervals of its descendants.
+///
+/// Edges are classified as in a directed graph, whatever `neighbours` the search was given:
+/// with `Undirected` every tree edge also shows up as a back edge from the child.
+pub trait Visitor<Id> {
+    // A new search tree starts at `root`, right before it is discovered
+    fn start(&mut self, _root: &Id) -> Control {
+        Control::Continue
+    }
+    fn discover(&mut self, _node: &Id, _time: usize) -> Control {
+        Control::Continue
+    }
+    fn finish(&mut self, _node: &Id, _time: usize) -> Control {
+        Control::Continue
+    }
+    // To a node not discovered yet
+    fn tree_edge(&mut self, _from: &Id, _to: &Id) -> Control {
+        Control::Continue
+    }
+    // To an ancestor that is still open (including `from` itself)
+    fn back_edge(&mut self, _from: &Id, _to: &Id) -> Control {
+        Control::Continue
+    }
+    // To a descendant that is already finished
+    fn forward_edge(&mut self, _from: &Id, _to: &Id) -> Control {
+        Control::Continue
+    }
+    // To a finished node in another subtree or an earlier tree
+    fn cross_edge(&mut self, _from: &Id, _to: &Id) -> Control {
+        Control::Continue
+    }
+}
+
+// Closures work as "on discover" visitors: `dfs_visit(.., &mut |id: &usize, _| ...)`
+impl<Id, F: FnMut(&Id, usize) -> Control> Visitor<Id> for F {
+    fn discover(&mut self, node: &Id, time: usize) -> Control {
+        self(node, time)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Visit<Id: Eq + std::hash::Hash> {
+    pub discovered: HashMap<Id, usize>,
+    pub finished: HashMap<Id, usize>,
+    // A visitor said Stop; nodes still open then have no finish time
+    pub stopped: bool,
+}
+
+// Depth first from each of `roots` in turn that isn't discovered by then. Pass
+// `graph.node_ids()` to cover the whole graph.
+pub fn dfs_visit<G, N, V>(
+    graph: &G,
+    roots: impl IntoIterator<Item = G::Id>,
+    mut neighbours: N,
+    visitor: &mut V,
+) -> Visit<G::Id>
+where
+    G: Adjacency + ?Sized,
+    N: Neighbours<G>,
+    V: Visitor<G::Id> + ?Sized,
+{
+    let mut visit = Visit {
+        discovered: HashMap::default(),
+        finished: HashMap::default(),
+        stopped: false,
+    };
+    let mut time = 0;
+    // Open nodes with their neighbours and how far through those we are. Neighbour lists are
+    // copied out because a provider hands out one borrowing iterator at a time.
+    let mut work: Vec<(G::Id, Vec<G::Id>, usize)> = Vec::new();
+
+    macro_rules! check {
+        ($control:expr) => {
+            match $control {
+                Control::Stop => {
+                    visit.stopped = true;
+                    return visit;
+                }
+                control => control,
+            }
+        };
+    }
+    // Discovers `node` and pushes it, or finishes it straight away if the visitor says Skip
+    macro_rules! open {
+        ($node:expr) => {{
+            let node = $node;
+            visit.discovered.insert(node.clone(), time);
+            time += 1;
+            if check!(visitor.discover(&node, time - 1)) == Control::Skip {
+                visit.finished.insert(node.clone(), time);
+                time += 1;
+                check!(visitor.finish(&node, time - 1));
+            } else {
+                let next: Vec<G::Id> = neighbours.neighbours(graph, &node).cloned().collect();
+                work.push((node, next, 0));
+            }
+        }};
+    }
+
+    for root in roots {
+        if visit.discovered.contains_key(&root) {
+            continue;
+        }
+        check!(visitor.start(&root));
+        open!(root);
+        while let Some((node, next, i)) = work.last_mut() {
+            if *i == next.len() {
+                let node = work.pop().unwrap().0;
+                visit.finished.insert(node.clone(), time);
+                time += 1;
+                check!(visitor.finish(&node, time - 1));
+                continue;
+            }
+            let to = next[*i].clone();
+            *i += 1;
+            let from = node.clone();
+            match (visit.discovered.get(&to), visit.finished.contains_key(&to)) {
+                (None, _) => {
+                    if check!(visitor.tree_edge(&from, &to)) != Control::Skip {
+                        open!(to);
+                    }
+                }
+                (Some(_), false) => {
+                    check!(visitor.back_edge(&from, &to));
+                }
+                (Some(&t), true) if t > visit.discovered[&from] => {
+                    check!(visitor.forward_edge(&from, &to));
+                }
+                (Some(_), true) => {
+                    check!(visitor.cross_edge(&from, &to));
+                }
+            }
+        }
+    }
+    visit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Forward, Graph};
+
+    // Every event, in order, as text
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl Visitor<char> for Log {
+        fn start(&mut self, root: &char) -> Control {
+            self.0.push(format!("start {}", root));
+            Control::Continue
+        }
+        fn discover(&mut self, node: &char, time: usize) -> Control {
+            self.0.push(format!("discover {} {}", node, time));
+            Control::Continue
+        }
+        fn finish(&mut self, node: &char, time: usize) -> Control {
+            self.0.push(format!("finish {} {}", node, time));
+            Control::Continue
+        }
+        fn tree_edge(&mut self, from: &char, to: &char) -> Control {
+            self.0.push(format!("tree {}{}", from, to));
+            Control::Continue
+        }
+        fn back_edge(&mut self, from: &char, to: &char) -> Control {
+            self.0.push(format!("back {}{}", from, to));
+            Control::Continue
+        }
+        fn forward_edge(&mut self, from: &char, to: &char) -> Control {
+            self.0.push(format!("forward {}{}", from, to));
+            Control::Continue
+        }
+        fn cross_edge(&mut self, from: &char, to: &char) -> Control {
+            self.0.push(format!("cross {}{}", from, to));
+            Control::Continue
+        }
+    }
+
+    #[test]
+    fn classifies_every_edge() {
+        // a -> b -> c -> a with a shortcut a -> c, then d -> c and a self loop on d: one of each
+        let mut graph: Graph<char> = Graph::new();
+        for (from, to) in [
+            ('a', 'b'),
+            ('b', 'c'),
+            ('c', 'a'),
+            ('a', 'c'),
+            ('d', 'c'),
+            ('d', 'd'),
+        ] {
+            graph.add_edge(from, to);
+        }
+        let mut log = Log::default();
+        let visit = dfs_visit(&graph, vec!['a', 'd'], Forward, &mut log);
+        assert_eq!(
+            log.0,
+            vec![
+                "start a",
+                "discover a 0",
+                "tree ab",
+                "discover b 1",
+                "tree bc",
+                "discover c 2",
+                "back ca",
+                "finish c 3",
+                "finish b 4",
+                "forward ac",
+                "finish a 5",
+                "start d",
+                "discover d 6",
+                "cross dc",
+                "back dd",
+                "finish d 7",
+            ]
+        );
+        assert!(!visit.stopped);
+        // Parenthesis theorem: descendants' intervals nest inside their ancestors'
+        let interval = |n: char| (visit.discovered[&n], visit.finished[&n]);
+        assert!(interval('a').0 < interval('c').0 && interval('c').1 < interval('a').1);
+    }
+
+    #[test]
+    fn visitors_can_stop_and_skip() {
+        // Cycle detection is a visitor that stops at the first back edge
+        struct FirstBackEdge(Option<(usize, usize)>);
+        impl Visitor<usize> for FirstBackEdge {
+            fn back_edge(&mut self, from: &usize, to: &usize) -> Control {
+                self.0 = Some((*from, *to));
+                Control::Stop
+            }
+        }
+        let mut graph: Graph<usize> = Graph::new();
+        for (from, to) in [(1, 2), (2, 3), (3, 4), (4, 2), (4, 5)] {
+            graph.add_edge(from, to);
+        }
+        let mut cycle = FirstBackEdge(None);
+        let visit = dfs_visit(&graph, Some(1), Forward, &mut cycle);
+        assert_eq!(cycle.0, Some((4, 2)));
+        assert!(visit.stopped);
+        assert!(!visit.finished.contains_key(&4));
+
+        // A closure is a discover callback; Skip leaves the subtree below 3 alone
+        let mut seen = Vec::new();
+        let visit = dfs_visit(&graph, Some(1), Forward, &mut |&id: &usize, _| {
+            seen.push(id);
+            if id == 3 {
+                Control::Skip
+            } else {
+                Control::Continue
+            }
+        });
+        assert_eq!(seen, vec![1, 2, 3]);
+        assert_eq!(visit.finished.len(), 3);
+
+        // Searching for a node ends as soon as it's found, unlike dfs_finish_time's search
+        let mut found = None;
+        dfs_visit(
+            &graph,
+            graph.node_ids(),
+            Forward,
+            &mut |&id: &usize, time| {
+                if id == 4 {
+                    found = Some(time);
+                    Control::Stop
+                } else {
+                    Control::Continue
+                }
+            },
+        );
+        assert!(found.is_some());
+    }
+
+    #[test]
+    fn deep_graphs_dont_overflow() {
+        let n = 100_000;
+        let mut graph: Graph<usize> = Graph::new();
+        for i in 0..n {
+            graph.add_edge(i, i + 1);
+        }
+        let visit = dfs_visit(&graph, Some(0), Forward, &mut |_: &usize, _| {
+            Control::Continue
+        });
+        assert_eq!(visit.finished[&0], 2 * n + 1);
+    }
+}
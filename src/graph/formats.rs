@@ -0,0 +1,1533 @@
+use super::parse::{Line, LoadError, Mode, ParseError, Records, Token};
+use super::Graph;
+use fxhash::FxHashMap as HashMap;
+use fxhash::FxHashSet as HashSet;
+use std::fmt::{self, Display, Write as _};
+use std::hash::Hash;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+/// File formats a graph can be read from and written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    // Graphviz, `digraph { a -> b [weight=3]; }`
+    Dot,
+    // DIMACS shortest path, `p sp n m` then `a u v w` arcs, nodes numbered 1..=n
+    Dimacs,
+    GraphMl,
+    // `u v` per line, directed (SCC.txt)
+    EdgeList,
+    // `v u u u` per line, undirected, every edge listed at both ends (kargerMinCut.txt)
+    Adjacency,
+    // `v u,w u,w` per line, undirected (dijkstraData.txt)
+    WeightedAdjacency,
+}
+
+impl Format {
+    pub const ALL: [Format; 6] = [
+        Format::Dot,
+        Format::Dimacs,
+        Format::GraphMl,
+        Format::EdgeList,
+        Format::Adjacency,
+        Format::WeightedAdjacency,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Dot => "dot",
+            Format::Dimacs => "dimacs",
+            Format::GraphMl => "graphml",
+            Format::EdgeList => "edges",
+            Format::Adjacency => "adjacency",
+            Format::WeightedAdjacency => "weighted",
+        }
+    }
+
+    // None for .txt and friends, where only the contents can tell
+    pub fn from_extension(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "dot" | "gv" => Some(Format::Dot),
+            "gr" => Some(Format::Dimacs),
+            "graphml" | "xml" => Some(Format::GraphMl),
+            _ => None,
+        }
+    }
+
+    pub fn sniff(text: &str) -> Format {
+        // By characters, since 16 bytes could end in the middle of one
+        let lower = text
+            .trim_start()
+            .chars()
+            .take(16)
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if lower.starts_with("<?xml") || lower.starts_with("<graphml") {
+            return Format::GraphMl;
+        }
+        if ["digraph", "graph", "strict"]
+            .iter()
+            .any(|w| lower.starts_with(w))
+        {
+            return Format::Dot;
+        }
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .take(20)
+            .collect();
+        if lines
+            .iter()
+            .any(|l| l.starts_with("p sp") || l.starts_with("a "))
+            && lines
+                .iter()
+                .all(|l| l.starts_with("c") || l.starts_with("p ") || l.starts_with("a "))
+        {
+            return Format::Dimacs;
+        }
+        if lines.iter().any(|l| l.contains(',')) {
+            Format::WeightedAdjacency
+        } else if lines.iter().all(|l| l.split_whitespace().count() == 2) {
+            Format::EdgeList
+        } else {
+            Format::Adjacency
+        }
+    }
+
+    pub fn detect(path: &Path, text: &str) -> Format {
+        Format::from_extension(path).unwrap_or_else(|| Format::sniff(text))
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Format::ALL
+            .iter()
+            .copied()
+            .find(|f| f.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Format::ALL.iter().map(|f| f.name()).collect();
+                format!(
+                    "unknown graph format {:?}, expected one of {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge<T> {
+    pub from: T,
+    pub to: T,
+    pub weight: Option<i64>,
+}
+
+/// A graph as it is in a file: nodes in file order, edges in file order (undirected ones
+/// once), weights where the format has them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphData<T> {
+    pub directed: bool,
+    pub nodes: Vec<T>,
+    pub edges: Vec<Edge<T>>,
+}
+
+impl<T: Clone + Eq + Hash> GraphData<T> {
+    pub fn new(directed: bool) -> Self {
+        GraphData {
+            directed,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    // Directed, unweighted, with nodes and edges in map order.
+    pub fn from_graph<W>(graph: &Graph<T, W>) -> Self {
+        Self::from_weighted_graph(graph, |_| None)
+    }
+
+    // Like `from_graph`, with `weight` saying what goes into the file for each payload.
+    pub fn from_weighted_graph<W>(graph: &Graph<T, W>, weight: impl Fn(&W) -> Option<i64>) -> Self {
+        let mut data = GraphData::new(true);
+        for node in graph.nodes.values() {
+            data.nodes.push(node.id.clone());
+            for (to, w) in node.weighted_edges() {
+                data.edges.push(Edge {
+                    from: node.id.clone(),
+                    to: to.clone(),
+                    weight: weight(w),
+                });
+            }
+        }
+        data
+    }
+
+    // Undirected edges go both ways. Weights are dropped; see `to_weighted_graph`.
+    pub fn to_graph(&self) -> Graph<T> {
+        self.to_weighted_graph(|_| ())
+    }
+
+    // Undirected edges go both ways, with the same payload. `weight` turns what the file said,
+    // if anything, into the payload.
+    pub fn to_weighted_graph<W: Clone>(&self, weight: impl Fn(Option<i64>) -> W) -> Graph<T, W> {
+        let mut graph = Graph::new();
+        for node in &self.nodes {
+            graph.add_node(node);
+        }
+        for edge in &self.edges {
+            let w = weight(edge.weight);
+            graph.add_weighted_edge(edge.from.clone(), edge.to.clone(), w.clone());
+            if !self.directed && edge.from != edge.to {
+                graph.add_weighted_edge(edge.to.clone(), edge.from.clone(), w);
+            }
+        }
+        graph
+    }
+
+    fn add_node(&mut self, seen: &mut HashSet<T>, id: &T) {
+        if seen.insert(id.clone()) {
+            self.nodes.push(id.clone());
+        }
+    }
+}
+
+// Where the text came from, for messages, and what to do with the parts that don't parse
+struct Reader<'a> {
+    file: &'a Path,
+    mode: Mode,
+    warnings: Vec<ParseError>,
+}
+
+impl Reader<'_> {
+    // Stops with `error`, or in lenient mode notes it and lets the caller skip what it's about
+    fn skip(&mut self, error: ParseError) -> Result<(), ParseError> {
+        match self.mode {
+            Mode::Strict => Err(error),
+            Mode::Lenient => {
+                self.warnings.push(error);
+                Ok(())
+            }
+        }
+    }
+
+    fn records<R>(
+        &self,
+        text: String,
+        parse: fn(&Line<'_>) -> Result<R, ParseError>,
+    ) -> Records<R> {
+        Records::new(self.file, io::Cursor::new(text), self.mode, parse)
+    }
+
+    // The lines `records` skipped join the other warnings
+    fn finish<R>(&mut self, records: Records<R>) -> Result<(), ParseError> {
+        match records.finish() {
+            Ok(warnings) => {
+                self.warnings.extend(warnings);
+                Ok(())
+            }
+            Err(LoadError::Parse(e)) => Err(e),
+            Err(LoadError::Io { error, .. }) => unreachable!("reading a String failed: {}", error),
+        }
+    }
+}
+
+/// A node number from the course files, which count from 1; `0` doesn't parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Number(pub usize);
+
+impl Number {
+    // Where the node goes in a 0-indexed vector
+    pub fn index(self) -> usize {
+        self.0 - 1
+    }
+}
+
+impl FromStr for Number {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s.parse() {
+            Ok(0) | Err(_) => Err(()),
+            Ok(n) => Ok(Number(n)),
+        }
+    }
+}
+
+// `from to`: SCC.txt and other edge lists
+pub fn edge_line<T: FromStr>(line: &Line<'_>) -> Result<(T, T), ParseError> {
+    let mut tokens = line.tokens();
+    let from = line.parse(tokens.next(), "a 'from' node")?;
+    let to = line.parse(tokens.next(), "a 'to' node")?;
+    line.end(tokens)?;
+    Ok((from, to))
+}
+
+// `v u u u`: kargerMinCut.txt
+pub fn adjacency_line<T: FromStr>(line: &Line<'_>) -> Result<(T, Vec<T>), ParseError> {
+    let mut tokens = line.tokens();
+    let head = line.parse(tokens.next(), "a node")?;
+    let list = tokens
+        .map(|token| line.parse(Some(token), "a node"))
+        .collect::<Result<_, _>>()?;
+    Ok((head, list))
+}
+
+// A node and its weighted out edges
+pub type AdjacencyLine<T, W> = (T, Vec<(T, W)>);
+
+// `v u,w u,w`: dijkstraData.txt
+pub fn weighted_adjacency_line<T: FromStr, W: FromStr>(
+    line: &Line<'_>,
+) -> Result<AdjacencyLine<T, W>, ParseError> {
+    let mut tokens = line.tokens();
+    let head = line.parse(tokens.next(), "a node")?;
+    let list = tokens
+        .map(|token| {
+            let (to, weight) = token
+                .split_once(',')
+                .ok_or_else(|| line.error(Some(token), "expected 'node,weight'"))?;
+            Ok((
+                line.parse(Some(to), "a node")?,
+                line.parse(Some(weight), "a weight")?,
+            ))
+        })
+        .collect::<Result<_, ParseError>>()?;
+    Ok((head, list))
+}
+
+// Strict, with the format's name in angle brackets where messages would name the file
+pub fn read_str<T>(text: &str, format: Format) -> Result<GraphData<T>, ParseError>
+where
+    T: FromStr + Clone + Eq + Hash,
+{
+    let name = format!("<{}>", format);
+    read_text(text.to_string(), Path::new(&name), format, Mode::Strict).map(|(data, _)| data)
+}
+
+// `file` is only for messages. Lenient mode skips the lines, DOT statements and GraphML
+// elements it can't read and returns them as warnings; a file whose structure is broken, like
+// a DOT graph that never closes or an unterminated GraphML tag, is an error either way.
+pub fn read_text<T>(
+    text: String,
+    file: &Path,
+    format: Format,
+    mode: Mode,
+) -> Result<(GraphData<T>, Vec<ParseError>), ParseError>
+where
+    T: FromStr + Clone + Eq + Hash,
+{
+    let mut reader = Reader {
+        file,
+        mode,
+        warnings: Vec::new(),
+    };
+    let data = match format {
+        Format::EdgeList => read_edge_list(&mut reader, text)?,
+        Format::Adjacency | Format::WeightedAdjacency => read_adjacency(&mut reader, text, format)?,
+        Format::Dimacs => read_dimacs(&mut reader, text)?,
+        Format::Dot => read_dot(&mut reader, &text)?,
+        Format::GraphMl => read_graphml(&mut reader, &text)?,
+    };
+    Ok((data, reader.warnings))
+}
+
+// Reads `path` in whatever format its extension or contents say.
+pub fn load<T>(
+    path: &Path,
+    format: Option<Format>,
+    mode: Mode,
+) -> Result<(Format, GraphData<T>, Vec<ParseError>), LoadError>
+where
+    T: FromStr + Clone + Eq + Hash,
+{
+    let text = std::fs::read_to_string(path).map_err(|error| LoadError::Io {
+        file: path.to_path_buf(),
+        error,
+    })?;
+    let format = format.unwrap_or_else(|| Format::detect(path, &text));
+    let (data, warnings) = read_text(text, path, format, mode)?;
+    Ok((format, data, warnings))
+}
+
+pub fn save<T: Display>(
+    data: &GraphData<T>,
+    path: &Path,
+    format: Option<Format>,
+) -> Result<Format, crate::cli::Error> {
+    let format = format
+        .or_else(|| Format::from_extension(path))
+        .ok_or_else(|| {
+            format!(
+                "can't tell the format of {} from its extension",
+                path.display()
+            )
+        })?;
+    std::fs::write(path, write_string(data, format))?;
+    Ok(format)
+}
+
+fn read_edge_list<T>(reader: &mut Reader<'_>, text: String) -> Result<GraphData<T>, ParseError>
+where
+    T: FromStr + Clone + Eq + Hash,
+{
+    let mut data = GraphData::new(true);
+    let mut seen = HashSet::default();
+    let mut records = reader.records(text, edge_line::<T>);
+    for (from, to) in &mut records {
+        data.add_node(&mut seen, &from);
+        data.add_node(&mut seen, &to);
+        data.edges.push(Edge {
+            from,
+            to,
+            weight: None,
+        });
+    }
+    reader.finish(records)?;
+    Ok(data)
+}
+
+// Each undirected edge is listed at both of its ends; keep it once, where it's seen first.
+fn read_adjacency<T>(
+    reader: &mut Reader<'_>,
+    text: String,
+    format: Format,
+) -> Result<GraphData<T>, ParseError>
+where
+    T: FromStr + Clone + Eq + Hash,
+{
+    let mut data = GraphData::new(false);
+    let mut seen = HashSet::default();
+    // Edges listed at one end so far, waiting for the other
+    let mut pending: HashMap<(T, T), usize> = HashMap::default();
+    let mut add = |head: T, list: Vec<(T, Option<i64>)>| {
+        data.add_node(&mut seen, &head);
+        for (to, weight) in list {
+            match pending.get_mut(&(to.clone(), head.clone())) {
+                Some(count) if *count > 0 => *count -= 1,
+                _ => {
+                    *pending.entry((head.clone(), to.clone())).or_default() += 1;
+                    data.edges.push(Edge {
+                        from: head.clone(),
+                        to,
+                        weight,
+                    });
+                }
+            }
+        }
+    };
+    if format == Format::WeightedAdjacency {
+        let mut records = reader.records(text, weighted_adjacency_line::<T, i64>);
+        for (head, list) in &mut records {
+            add(
+                head,
+                list.into_iter().map(|(to, w)| (to, Some(w))).collect(),
+            );
+        }
+        reader.finish(records)?;
+    } else {
+        let mut records = reader.records(text, adjacency_line::<T>);
+        for (head, list) in &mut records {
+            add(head, list.into_iter().map(|to| (to, None)).collect());
+        }
+        reader.finish(records)?;
+    }
+    // Neighbours that never got a line of their own are still nodes
+    for edge in data.edges.clone() {
+        data.add_node(&mut seen, &edge.to);
+    }
+    Ok(data)
+}
+
+enum DimacsLine<T> {
+    Comment,
+    // `c n <number> <id>`, written by us
+    Label(usize, T),
+    // `p sp <nodes> <arcs>`
+    Problem(usize),
+    // `a <from> <to> <weight>`, with where it is for errors about the numbers
+    Arc {
+        from: usize,
+        to: usize,
+        weight: i64,
+        line: usize,
+        columns: (usize, usize),
+    },
+}
+
+fn dimacs_line<T: FromStr>(line: &Line<'_>) -> Result<DimacsLine<T>, ParseError> {
+    let mut tokens = line.tokens();
+    let first = tokens.next();
+    match first.map(|t| t.text) {
+        Some("c") => match (tokens.next(), tokens.next(), tokens.next()) {
+            (Some(n), Some(number), Some(id)) if n.text == "n" => {
+                // The id is the rest of the line, spaces and all
+                let rest: String = line.text.chars().skip(id.column - 1).collect();
+                let id = Token {
+                    column: id.column,
+                    text: rest.trim_end(),
+                };
+                Ok(DimacsLine::Label(
+                    line.parse(Some(number), "a node number")?,
+                    line.parse(Some(id), "a node id")?,
+                ))
+            }
+            _ => Ok(DimacsLine::Comment),
+        },
+        Some("p") => {
+            let sp = tokens.next();
+            if sp.map(|t| t.text) != Some("sp") {
+                return Err(line.error(sp, "expected `sp`"));
+            }
+            let nodes = line.parse(tokens.next(), "a node count")?;
+            line.parse::<usize>(tokens.next(), "an arc count")?;
+            line.end(tokens)?;
+            Ok(DimacsLine::Problem(nodes))
+        }
+        Some("a") => {
+            let (from, to) = (tokens.next(), tokens.next());
+            let arc = DimacsLine::Arc {
+                from: line.parse(from, "a node number")?,
+                to: line.parse(to, "a node number")?,
+                weight: line.parse(tokens.next(), "a weight")?,
+                line: line.number,
+                columns: (from.unwrap().column, to.unwrap().column),
+            };
+            line.end(tokens)?;
+            Ok(arc)
+        }
+        _ => Err(line.error(first, "expected a `c`, `p` or `a` line")),
+    }
+}
+
+fn read_dimacs<T>(reader: &mut Reader<'_>, text: String) -> Result<GraphData<T>, ParseError>
+where
+    T: FromStr + Clone + Eq + Hash,
+{
+    let file = reader.file;
+    let mut data = GraphData::new(true);
+    // `c n <i> <id>` comments, written by us, give the numbers their original ids back.
+    let mut labels: HashMap<usize, T> = HashMap::default();
+    let mut n = None;
+    let mut arcs = Vec::new();
+    let mut records = reader.records(text, dimacs_line::<T>);
+    for record in &mut records {
+        let (from, to, weight, line, columns) = match record {
+            DimacsLine::Comment => continue,
+            DimacsLine::Label(number, id) => {
+                labels.insert(number, id);
+                continue;
+            }
+            DimacsLine::Problem(nodes) => {
+                n = Some(nodes);
+                continue;
+            }
+            DimacsLine::Arc {
+                from,
+                to,
+                weight,
+                line,
+                columns,
+            } => (from, to, weight, line, columns),
+        };
+        let problem = match n {
+            None => Some((columns.0, from, "arc before the `p sp` line".to_string())),
+            Some(n) if from == 0 || from > n => {
+                Some((columns.0, from, format!("nodes are numbered 1..={}", n)))
+            }
+            Some(n) if to == 0 || to > n => {
+                Some((columns.1, to, format!("nodes are numbered 1..={}", n)))
+            }
+            Some(_) => None,
+        };
+        match problem {
+            Some((column, number, message)) => reader.skip(ParseError::new(
+                file,
+                line,
+                column,
+                &number.to_string(),
+                message,
+            ))?,
+            None => arcs.push((from, to, weight)),
+        }
+    }
+    reader.finish(records)?;
+    let n = n.ok_or_else(|| ParseError::whole_file(file, "no `p sp` line"))?;
+    let id = |number: usize| -> Result<T, ParseError> {
+        match labels.get(&number) {
+            Some(label) => Ok(label.clone()),
+            None => number.to_string().parse().map_err(|_| {
+                ParseError::whole_file(file, format!("node {} has no `c n` id", number))
+            }),
+        }
+    };
+    for number in 1..=n {
+        data.nodes.push(id(number)?);
+    }
+    for (from, to, weight) in arcs {
+        data.edges.push(Edge {
+            from: id(from)?,
+            to: id(to)?,
+            weight: Some(weight),
+        });
+    }
+    Ok(data)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DotToken {
+    // Whether it was quoted: only bare ids can be keywords
+    Id(String, bool),
+    Arrow,
+    Line,
+    Punct(char),
+}
+
+impl DotToken {
+    // As it was in the file, near enough, for messages
+    fn text(&self) -> String {
+        match self {
+            DotToken::Id(id, false) => id.clone(),
+            DotToken::Id(id, true) => format!("{:?}", id),
+            DotToken::Arrow => "->".to_string(),
+            DotToken::Line => "--".to_string(),
+            DotToken::Punct(c) => c.to_string(),
+        }
+    }
+}
+
+// Tokens with their line and column
+fn dot_tokens(file: &Path, text: &str) -> Result<Vec<(DotToken, usize, usize)>, ParseError> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = text.chars().collect();
+    // Where the current line starts, for columns
+    let (mut i, mut line, mut line_start) = (0, 1, 0);
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let column = i - line_start + 1;
+        match c {
+            '\n' => {
+                line += 1;
+                i += 1;
+                line_start = i;
+            }
+            c if c.is_whitespace() => i += 1,
+            '#' if i == 0 || chars[i - 1] == '\n' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if next == Some('/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if next == Some('*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    if chars[i] == '\n' {
+                        line += 1;
+                        line_start = i + 1;
+                    }
+                    i += 1;
+                }
+                i += 2;
+            }
+            '-' if next == Some('>') => {
+                tokens.push((DotToken::Arrow, line, column));
+                i += 2;
+            }
+            '-' if next == Some('-') => {
+                tokens.push((DotToken::Line, line, column));
+                i += 2;
+            }
+            '"' => {
+                let start = line;
+                let mut s = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(ParseError::new(
+                                file,
+                                start,
+                                column,
+                                "\"",
+                                "unterminated string",
+                            ))
+                        }
+                        Some('"') => break,
+                        Some('\\') if chars.get(i + 1) == Some(&'"') => {
+                            s.push('"');
+                            i += 1;
+                        }
+                        Some(&c) => {
+                            if c == '\n' {
+                                line += 1;
+                                line_start = i + 1;
+                            }
+                            s.push(c);
+                        }
+                    }
+                    i += 1;
+                }
+                tokens.push((DotToken::Id(s, true), start, column));
+                i += 1;
+            }
+            '{' | '}' | '[' | ']' | ';' | ',' | '=' | ':' => {
+                tokens.push((DotToken::Punct(c), line, column));
+                i += 1;
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.')
+                    || (i == start && chars[i] == '-')
+                {
+                    i += 1;
+                }
+                let id = chars[start..i].iter().collect();
+                tokens.push((DotToken::Id(id, false), line, column));
+            }
+            '<' => {
+                return Err(ParseError::new(
+                    file,
+                    line,
+                    column,
+                    "<",
+                    "HTML labels are not supported",
+                ))
+            }
+            c => {
+                return Err(ParseError::new(
+                    file,
+                    line,
+                    column,
+                    &c.to_string(),
+                    "unexpected character",
+                ))
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// Enough of DOT for what viewers and other tools write: node and edge statements, edge chains,
+// attribute lists (weight or label become the edge weight), subgraphs flattened, and
+// graph/node/edge defaults and `a = b` statements skipped. In lenient mode a statement that
+// doesn't parse is skipped up to its `;` or the end of its line.
+fn read_dot<T>(reader: &mut Reader<'_>, text: &str) -> Result<GraphData<T>, ParseError>
+where
+    T: FromStr + Clone + Eq + Hash,
+{
+    let file = reader.file;
+    let tokens = dot_tokens(file, text)?;
+    let mut pos = 0;
+    let at = |pos: usize| tokens.get(pos).map(|(t, _, _)| t);
+    let error_at = |pos: usize, message: &str| match tokens.get(pos) {
+        Some((token, line, column)) => {
+            ParseError::new(file, *line, *column, &token.text(), message)
+        }
+        None => ParseError::whole_file(file, format!("{}, found the end of the file", message)),
+    };
+    let keyword = |pos: usize, word: &str| matches!(at(pos), Some(DotToken::Id(id, false)) if id.eq_ignore_ascii_case(word));
+
+    if keyword(pos, "strict") {
+        pos += 1;
+    }
+    let directed = if keyword(pos, "digraph") {
+        true
+    } else if keyword(pos, "graph") {
+        false
+    } else {
+        return Err(error_at(pos, "expected `graph` or `digraph`"));
+    };
+    pos += 1;
+    if let Some(DotToken::Id(..)) = at(pos) {
+        pos += 1;
+    }
+    if at(pos) != Some(&DotToken::Punct('{')) {
+        return Err(error_at(pos, "expected `{`"));
+    }
+    pos += 1;
+
+    let mut data = GraphData::new(directed);
+    let mut seen = HashSet::default();
+    let mut depth = 1;
+    // Attribute list at `pos`, if any: returns the weight it sets and where it ends.
+    let attributes = |mut pos: usize| -> Result<(Option<i64>, usize), ParseError> {
+        let mut weight = None;
+        while at(pos) == Some(&DotToken::Punct('[')) {
+            pos += 1;
+            loop {
+                match at(pos) {
+                    Some(DotToken::Punct(']')) => {
+                        pos += 1;
+                        break;
+                    }
+                    Some(DotToken::Punct(',')) | Some(DotToken::Punct(';')) => pos += 1,
+                    Some(DotToken::Id(key, _)) => {
+                        if at(pos + 1) != Some(&DotToken::Punct('=')) {
+                            pos += 1;
+                            continue;
+                        }
+                        let value = match at(pos + 2) {
+                            Some(DotToken::Id(value, _)) => value,
+                            _ => {
+                                return Err(error_at(
+                                    pos + 2,
+                                    &format!("expected a value for {}", key),
+                                ))
+                            }
+                        };
+                        if key == "weight" || (key == "label" && weight.is_none()) {
+                            if let Ok(w) = value.parse::<i64>() {
+                                weight = Some(w);
+                            } else if key == "weight" {
+                                return Err(error_at(pos + 2, "expected a weight"));
+                            }
+                        }
+                        pos += 3;
+                    }
+                    _ => return Err(error_at(pos, "unterminated attribute list")),
+                }
+            }
+        }
+        Ok((weight, pos))
+    };
+    // Node statement or edge chain at `pos`: the nodes, the weight and where it ends
+    let statement = |mut pos: usize| -> Result<(Vec<T>, Option<i64>, usize), ParseError> {
+        let mut chain = Vec::new();
+        loop {
+            let id = match at(pos) {
+                Some(DotToken::Id(id, _)) => id
+                    .parse::<T>()
+                    .map_err(|_| error_at(pos, "expected a node id"))?,
+                _ => return Err(error_at(pos, "expected a node id")),
+            };
+            chain.push(id);
+            pos += 1;
+            // Ports (`a:n`) don't matter for the graph
+            while at(pos) == Some(&DotToken::Punct(':')) {
+                pos += 2;
+            }
+            match at(pos) {
+                Some(DotToken::Arrow) if directed => pos += 1,
+                Some(DotToken::Line) if !directed => pos += 1,
+                Some(DotToken::Arrow) | Some(DotToken::Line) => {
+                    return Err(error_at(pos, "wrong edge operator for this kind of graph"))
+                }
+                _ => break,
+            }
+        }
+        let (weight, end) = attributes(pos)?;
+        Ok((chain, weight, end))
+    };
+    // Past the statement that starts at `pos`: its `;`, or up to a `}` or the next line
+    let skip_statement = |mut pos: usize| {
+        let line = tokens[pos].1;
+        while let Some((token, l, _)) = tokens.get(pos) {
+            if *l > line || *token == DotToken::Punct('}') {
+                break;
+            }
+            pos += 1;
+            if *token == DotToken::Punct(';') {
+                break;
+            }
+        }
+        pos
+    };
+    while depth > 0 {
+        match at(pos) {
+            None => return Err(error_at(pos, "missing `}`")),
+            Some(DotToken::Punct('}')) => {
+                depth -= 1;
+                pos += 1;
+            }
+            Some(DotToken::Punct('{')) => {
+                depth += 1;
+                pos += 1;
+            }
+            Some(DotToken::Punct(';')) => pos += 1,
+            Some(DotToken::Id(..)) if keyword(pos, "subgraph") => {
+                pos += 1;
+                if let Some(DotToken::Id(..)) = at(pos) {
+                    pos += 1;
+                }
+            }
+            Some(DotToken::Id(..))
+                if ["graph", "node", "edge"]
+                    .iter()
+                    .any(|word| keyword(pos, word)) =>
+            {
+                match attributes(pos + 1) {
+                    Ok((_, end)) => pos = end,
+                    Err(e) => {
+                        reader.skip(e)?;
+                        pos = skip_statement(pos);
+                    }
+                }
+            }
+            Some(DotToken::Id(..)) if at(pos + 1) == Some(&DotToken::Punct('=')) => pos += 3,
+            Some(DotToken::Id(..)) => match statement(pos) {
+                Ok((chain, weight, end)) => {
+                    pos = end;
+                    for id in &chain {
+                        data.add_node(&mut seen, id);
+                    }
+                    for pair in chain.windows(2) {
+                        data.edges.push(Edge {
+                            from: pair[0].clone(),
+                            to: pair[1].clone(),
+                            weight,
+                        });
+                    }
+                }
+                Err(e) => {
+                    reader.skip(e)?;
+                    pos = skip_statement(pos);
+                }
+            },
+            Some(_) => {
+                reader.skip(error_at(pos, "unexpected token"))?;
+                pos += 1;
+            }
+        }
+    }
+    Ok(data)
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// `name="value"` pairs of the tag starting right after `<name`
+fn xml_attributes(tag: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::default();
+    let mut rest = tag;
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq]
+            .trim()
+            .rsplit(char::is_whitespace)
+            .next()
+            .unwrap_or("")
+            .to_string();
+        let after = rest[eq + 1..].trim_start();
+        let quote = match after.chars().next() {
+            Some(q @ '"') | Some(q @ '\'') => q,
+            _ => break,
+        };
+        let end = match after[1..].find(quote) {
+            Some(end) => end,
+            None => break,
+        };
+        attributes.insert(key, xml_unescape(&after[1..1 + end]));
+        rest = &after[end + 2..];
+    }
+    attributes
+}
+
+// Just the GraphML that matters here: keys, the first graph's edgedefault, nodes, edges and
+// their weight data. Anything else is skipped, and so is a node or edge that doesn't make
+// sense, in lenient mode.
+fn read_graphml<T>(reader: &mut Reader<'_>, text: &str) -> Result<GraphData<T>, ParseError>
+where
+    T: FromStr + Clone + Eq + Hash,
+{
+    let file = reader.file;
+    // Only worked out when something goes wrong, since it counts from the start every time
+    let error_at = |offset: usize, token: &str, message: &str| {
+        let before = &text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line = before.matches('\n').count() + 1;
+        let column = before[line_start..].chars().count() + 1;
+        ParseError::new(file, line, column, token, message)
+    };
+    let mut data = GraphData::new(true);
+    let mut seen = HashSet::default();
+    let mut weight_keys = HashSet::default();
+    // The open edge and where its tag starts
+    let mut pending_edge: Option<(Edge<T>, usize)> = None;
+    let mut data_key: Option<(String, usize)> = None;
+    let mut rest = 0;
+    let mut saw_graph = false;
+    while let Some(open) = text[rest..].find('<') {
+        let start = rest + open;
+        let end = match text[start..].find('>') {
+            Some(end) => start + end,
+            None => return Err(error_at(start, "<", "unterminated tag")),
+        };
+        rest = end + 1;
+        let tag = &text[start + 1..end];
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+        let closing = tag.starts_with('/');
+        let self_closing = tag.ends_with('/');
+        let attributes = xml_attributes(&tag[name.len().min(tag.len())..]);
+        // The id in attribute `key` of this tag
+        let id = |key: &str| -> Result<T, ParseError> {
+            match attributes.get(key) {
+                Some(id) => id
+                    .parse()
+                    .map_err(|_| error_at(start, id, &format!("expected a node id for {}", key))),
+                None => Err(error_at(
+                    start,
+                    name,
+                    &format!("expected a {} attribute", key),
+                )),
+            }
+        };
+        match (name, closing) {
+            ("key", false) if attributes.get("attr.name").map(String::as_str) == Some("weight") => {
+                if let Some(id) = attributes.get("id") {
+                    weight_keys.insert(id.clone());
+                }
+            }
+            ("graph", false) if !saw_graph => {
+                saw_graph = true;
+                data.directed =
+                    attributes.get("edgedefault").map(String::as_str) != Some("undirected");
+            }
+            ("node", false) => match id("id") {
+                Ok(id) => data.add_node(&mut seen, &id),
+                Err(e) => reader.skip(e)?,
+            },
+            ("edge", false) => {
+                let (from, to) = match (id("source"), id("target")) {
+                    (Ok(from), Ok(to)) => (from, to),
+                    (Err(e), _) | (_, Err(e)) => {
+                        reader.skip(e)?;
+                        continue;
+                    }
+                };
+                data.add_node(&mut seen, &from);
+                data.add_node(&mut seen, &to);
+                let edge = Edge {
+                    from,
+                    to,
+                    weight: None,
+                };
+                if self_closing {
+                    data.edges.push(edge);
+                } else {
+                    pending_edge = Some((edge, start));
+                }
+            }
+            ("edge", true) => {
+                if let Some((edge, _)) = pending_edge.take() {
+                    data.edges.push(edge);
+                }
+            }
+            ("data", false) if !self_closing => {
+                data_key = attributes.get("key").map(|k| (k.clone(), end + 1));
+            }
+            ("data", true) => {
+                if let (Some((key, from)), Some((edge, _))) =
+                    (data_key.take(), pending_edge.as_mut())
+                {
+                    if weight_keys.contains(&key) || key == "weight" {
+                        let value = xml_unescape(&text[from..start]);
+                        match value.trim().parse() {
+                            Ok(weight) => edge.weight = Some(weight),
+                            Err(_) => {
+                                reader.skip(error_at(from, value.trim(), "expected a weight"))?;
+                                // The edge goes with its weight
+                                pending_edge = None;
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some((_, start)) = pending_edge {
+        return Err(error_at(start, "<edge", "edge is never closed"));
+    }
+    if !saw_graph {
+        return Err(ParseError::whole_file(file, "no <graph> element"));
+    }
+    Ok(data)
+}
+
+// Every node's name in order, then any edge end that `nodes` left out: the fields are public, so
+// nothing stops an edge from naming a node that isn't listed
+fn node_names<T: Display>(data: &GraphData<T>) -> Vec<String> {
+    let mut names: Vec<String> = data.nodes.iter().map(|n| n.to_string()).collect();
+    let mut seen: HashSet<String> = names.iter().cloned().collect();
+    for edge in &data.edges {
+        for end in [&edge.from, &edge.to] {
+            let name = end.to_string();
+            if seen.insert(name.clone()) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+pub fn write_string<T: Display>(data: &GraphData<T>, format: Format) -> String {
+    let mut out = String::new();
+    // Writing into a String can't fail
+    let _ = write_into(data, format, &mut out);
+    out
+}
+
+fn write_into<T: Display>(data: &GraphData<T>, format: Format, out: &mut String) -> fmt::Result {
+    match format {
+        Format::EdgeList => {
+            for edge in &data.edges {
+                writeln!(out, "{} {}", edge.from, edge.to)?;
+                if !data.directed && edge.from.to_string() != edge.to.to_string() {
+                    writeln!(out, "{} {}", edge.to, edge.from)?;
+                }
+            }
+        }
+        Format::Adjacency | Format::WeightedAdjacency => {
+            // Every node gets a line; undirected edges are listed at both ends
+            let names = node_names(data);
+            let position: HashMap<&str, usize> = names
+                .iter()
+                .enumerate()
+                .map(|(i, n)| (n.as_str(), i))
+                .collect();
+            let mut lists: Vec<Vec<(String, Option<i64>)>> = vec![Vec::new(); names.len()];
+            for edge in &data.edges {
+                let (from, to) = (edge.from.to_string(), edge.to.to_string());
+                if let Some(&i) = position.get(from.as_str()) {
+                    lists[i].push((to.clone(), edge.weight));
+                }
+                if !data.directed && from != to {
+                    if let Some(&i) = position.get(to.as_str()) {
+                        lists[i].push((from, edge.weight));
+                    }
+                }
+            }
+            for (name, list) in names.iter().zip(lists) {
+                write!(out, "{}", name)?;
+                for (to, weight) in list {
+                    if format == Format::WeightedAdjacency {
+                        write!(out, "\t{},{}", to, weight.unwrap_or(1))?;
+                    } else {
+                        write!(out, "\t{}", to)?;
+                    }
+                }
+                writeln!(out)?;
+            }
+        }
+        Format::Dimacs => {
+            let names = node_names(data);
+            let number: HashMap<&str, usize> = names
+                .iter()
+                .enumerate()
+                .map(|(i, n)| (n.as_str(), i + 1))
+                .collect();
+            let arcs = if data.directed {
+                data.edges.len()
+            } else {
+                data.edges.len() * 2
+            };
+            writeln!(out, "c written by {}", env!("CARGO_PKG_NAME"))?;
+            writeln!(out, "p sp {} {}", names.len(), arcs)?;
+            // DIMACS only has numbers; keep the ids around unless they are the numbers already
+            for (i, name) in names.iter().enumerate() {
+                if *name != (i + 1).to_string() {
+                    writeln!(out, "c n {} {}", i + 1, name)?;
+                }
+            }
+            for edge in &data.edges {
+                let (from, to) = (
+                    number[edge.from.to_string().as_str()],
+                    number[edge.to.to_string().as_str()],
+                );
+                let weight = edge.weight.unwrap_or(1);
+                writeln!(out, "a {} {} {}", from, to, weight)?;
+                if !data.directed {
+                    writeln!(out, "a {} {} {}", to, from, weight)?;
+                }
+            }
+        }
+        Format::Dot => {
+            let (keyword, operator) = if data.directed {
+                ("digraph", "->")
+            } else {
+                ("graph", "--")
+            };
+            let quote = |id: &T| format!("\"{}\"", id.to_string().replace('"', "\\\""));
+            writeln!(out, "{} {{", keyword)?;
+            for node in &data.nodes {
+                writeln!(out, "  {};", quote(node))?;
+            }
+            for edge in &data.edges {
+                write!(
+                    out,
+                    "  {} {} {}",
+                    quote(&edge.from),
+                    operator,
+                    quote(&edge.to)
+                )?;
+                if let Some(weight) = edge.weight {
+                    write!(out, " [weight={}, label={}]", weight, weight)?;
+                }
+                writeln!(out, ";")?;
+            }
+            writeln!(out, "}}")?;
+        }
+        Format::GraphMl => {
+            writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+            writeln!(
+                out,
+                "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">"
+            )?;
+            writeln!(
+                out,
+                "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"long\"/>"
+            )?;
+            writeln!(
+                out,
+                "  <graph id=\"G\" edgedefault=\"{}\">",
+                if data.directed {
+                    "directed"
+                } else {
+                    "undirected"
+                }
+            )?;
+            for node in &data.nodes {
+                writeln!(out, "    <node id=\"{}\"/>", xml_escape(&node.to_string()))?;
+            }
+            for edge in &data.edges {
+                let (from, to) = (
+                    xml_escape(&edge.from.to_string()),
+                    xml_escape(&edge.to.to_string()),
+                );
+                match edge.weight {
+                    Some(weight) => writeln!(
+                        out,
+                        "    <edge source=\"{}\" target=\"{}\"><data key=\"weight\">{}</data></edge>",
+                        from, to, weight
+                    )?,
+                    None => writeln!(out, "    <edge source=\"{}\" target=\"{}\"/>", from, to)?,
+                }
+            }
+            writeln!(out, "  </graph>")?;
+            writeln!(out, "</graphml>")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(directed: bool) -> GraphData<String> {
+        let edge = |from: &str, to: &str, weight| Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            weight,
+        };
+        GraphData {
+            directed,
+            nodes: vec![
+                "a".into(),
+                "b \"quoted\" & <odd>".into(),
+                "c".into(),
+                "lonely".into(),
+            ],
+            edges: vec![
+                edge("a", "b \"quoted\" & <odd>", Some(3)),
+                edge("b \"quoted\" & <odd>", "c", Some(-4)),
+                edge("c", "a", Some(7)),
+            ],
+        }
+    }
+
+    fn numeric(directed: bool, weighted: bool) -> GraphData<usize> {
+        let edges = vec![(1, 2, 5), (1, 3, 2), (2, 3, 1), (3, 4, 9)];
+        GraphData {
+            directed,
+            nodes: vec![1, 2, 3, 4],
+            edges: edges
+                .into_iter()
+                .map(|(from, to, w)| Edge {
+                    from,
+                    to,
+                    weight: if weighted { Some(w) } else { None },
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        for &directed in &[true, false] {
+            for &format in &[Format::Dot, Format::GraphMl, Format::Dimacs] {
+                let data = sample(directed);
+                let text = write_string(&data, format);
+                let mut back: GraphData<String> = read_str(&text, format).unwrap();
+                if format == Format::Dimacs {
+                    // Arcs only, and both ways for undirected
+                    assert!(back.directed);
+                    back.edges.retain(|e| data.edges.contains(e));
+                    back.directed = directed;
+                }
+                assert_eq!(back, data, "{} {}", format, text);
+                assert_eq!(Format::sniff(&text), format);
+            }
+        }
+        let edges = numeric(true, false);
+        let text = write_string(&edges, Format::EdgeList);
+        assert_eq!(Format::sniff(&text), Format::EdgeList);
+        assert_eq!(read_str::<usize>(&text, Format::EdgeList).unwrap(), edges);
+
+        let undirected = numeric(false, false);
+        let text = write_string(&undirected, Format::Adjacency);
+        assert_eq!(text.lines().next(), Some("1\t2\t3"));
+        assert_eq!(Format::sniff(&text), Format::Adjacency);
+        assert_eq!(
+            read_str::<usize>(&text, Format::Adjacency).unwrap(),
+            undirected
+        );
+
+        let weighted = numeric(false, true);
+        let text = write_string(&weighted, Format::WeightedAdjacency);
+        assert_eq!(text.lines().next(), Some("1\t2,5\t3,2"));
+        assert_eq!(Format::sniff(&text), Format::WeightedAdjacency);
+        assert_eq!(
+            read_str::<usize>(&text, Format::WeightedAdjacency).unwrap(),
+            weighted
+        );
+    }
+
+    #[test]
+    fn writes_nodes_only_edges_mention() {
+        // 4 isn't in `nodes`, only at the end of an edge
+        let mut data = numeric(true, true);
+        data.nodes.pop();
+        let text = write_string(&data, Format::Dimacs);
+        assert!(text.contains("p sp 4 4\n"), "{}", text);
+        assert_eq!(
+            read_str::<usize>(&text, Format::Dimacs).unwrap(),
+            numeric(true, true)
+        );
+        let text = write_string(&data, Format::WeightedAdjacency);
+        assert_eq!(text.lines().last(), Some("4"));
+    }
+
+    #[test]
+    fn reads_course_files() {
+        let graph: GraphData<usize> =
+            read_str("1\t2\t3\t\n2\t1\t3\t\n3\t1\t2\t\n", Format::Adjacency).unwrap();
+        assert!(!graph.directed);
+        assert_eq!(graph.edges.len(), 3);
+        assert_eq!(graph.to_graph().nodes[&1].edges, vec![2, 3]);
+        let graph: GraphData<usize> =
+            read_str("1\t2,10\t\n2\t1,10\t\n", Format::WeightedAdjacency).unwrap();
+        assert_eq!(
+            graph.edges,
+            vec![Edge {
+                from: 1,
+                to: 2,
+                weight: Some(10)
+            }]
+        );
+        let weighted = graph.to_weighted_graph(|w| w.unwrap_or(1));
+        assert_eq!(weighted.weight(&2, &1), Some(&10));
+        assert_eq!(
+            GraphData::from_weighted_graph(&weighted, |&w| Some(w))
+                .edges
+                .len(),
+            2
+        );
+        // The course loaders read the same lines with numbers that count from 1
+        let file = Path::new("kargerMinCut.txt");
+        let text = io::Cursor::new("1\t2\t3\n2\t1\t0\n");
+        let (lines, warnings) = Records::new(file, text, Mode::Lenient, adjacency_line::<Number>)
+            .read_all()
+            .unwrap();
+        assert_eq!(lines, vec![(Number(1), vec![Number(2), Number(3)])]);
+        assert_eq!(
+            warnings[0].to_string(),
+            "kargerMinCut.txt:2:5: expected a node, found \"0\""
+        );
+        assert_eq!(Number(3).index(), 2);
+    }
+
+    #[test]
+    fn reads_hand_written_dot() {
+        let text = r#"
+            // comment
+            strict digraph "name" {
+                graph [rankdir=LR]; node [shape=box]
+                rankdir = LR
+                a -> b -> c [label=2, color="red"]
+                /* block
+                   comment */
+                subgraph cluster_x { d; e -> a [weight=9] }
+                "f g":n -> a
+            }
+        "#;
+        let data: GraphData<String> = read_str(text, Format::Dot).unwrap();
+        assert!(data.directed);
+        assert_eq!(data.nodes, vec!["a", "b", "c", "d", "e", "f g"]);
+        let edges: Vec<(&str, &str, Option<i64>)> = data
+            .edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str(), e.weight))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("a", "b", Some(2)),
+                ("b", "c", Some(2)),
+                ("e", "a", Some(9)),
+                ("f g", "a", None)
+            ]
+        );
+        let e = read_str::<String>("graph { a -> b }", Format::Dot).unwrap_err();
+        assert!(e.to_string().contains("wrong edge operator"));
+    }
+
+    #[test]
+    fn quoted_keywords_are_nodes() {
+        let edge = |from: &str, to: &str| Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            weight: Some(1),
+        };
+        let data = GraphData {
+            directed: true,
+            nodes: vec![
+                "node".to_string(),
+                "edge".to_string(),
+                "graph".to_string(),
+                "subgraph".to_string(),
+                "Node".to_string(),
+            ],
+            edges: vec![
+                edge("node", "edge"),
+                edge("graph", "subgraph"),
+                edge("subgraph", "Node"),
+            ],
+        };
+        let text = write_string(&data, Format::Dot);
+        assert_eq!(read_str::<String>(&text, Format::Dot).unwrap(), data);
+        // Bare, they're keywords whatever their case
+        let text = "DiGraph { NODE [shape=box]; Edge [weight=2]; SubGraph s { a -> b } }";
+        let data: GraphData<String> = read_str(text, Format::Dot).unwrap();
+        assert_eq!(data.nodes, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn detects_and_reports() {
+        assert_eq!(
+            Format::from_extension(Path::new("x/g.GV")),
+            Some(Format::Dot)
+        );
+        assert_eq!(
+            Format::from_extension(Path::new("g.gr")),
+            Some(Format::Dimacs)
+        );
+        assert_eq!(Format::from_extension(Path::new("SCC.txt")), None);
+        assert_eq!(Format::sniff("c hi\np sp 2 1\na 1 2 3\n"), Format::Dimacs);
+        // The 16th byte is in the middle of an é
+        assert_eq!(Format::sniff("graph{\"éééééééé\" }"), Format::Dot);
+        assert_eq!("graphml".parse::<Format>(), Ok(Format::GraphMl));
+        assert!("yaml".parse::<Format>().is_err());
+
+        let e = read_str::<usize>("1 2\n3\n", Format::EdgeList).unwrap_err();
+        assert_eq!((e.line, e.column), (2, 2));
+        let e = read_str::<usize>("1 2\nx 3\n", Format::EdgeList).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "<edges>:2:1: expected a 'from' node, found \"x\""
+        );
+        let e = read_str::<usize>("1\t2,\n", Format::WeightedAdjacency).unwrap_err();
+        assert_eq!(
+            (e.line, e.column, e.message.as_str()),
+            (1, 5, "expected a weight")
+        );
+        let e = read_str::<usize>("p sp 2 1\na 1 3 1\n", Format::Dimacs).unwrap_err();
+        assert_eq!((e.line, e.column, e.token.as_str()), (2, 5, "3"));
+        let e = read_str::<usize>("c nothing\n", Format::Dimacs).unwrap_err();
+        assert_eq!(e.to_string(), "<dimacs>: no `p sp` line");
+        let e = read_str::<String>("digraph {\n  a -> b\n  c -- d\n}", Format::Dot).unwrap_err();
+        assert_eq!((e.line, e.column, e.token.as_str()), (3, 5, "--"));
+        let e = read_str::<String>("<graphml><graph>\n  <node/>", Format::GraphMl).unwrap_err();
+        assert_eq!((e.line, e.column, e.token.as_str()), (2, 3, "node"));
+    }
+
+    #[test]
+    fn lenient_skips_what_it_can() {
+        let file = Path::new("g");
+        let read = |text: &str, format| {
+            read_text::<usize>(text.to_string(), file, format, Mode::Lenient).unwrap()
+        };
+        let positions = |warnings: &[ParseError]| -> Vec<(usize, usize)> {
+            warnings.iter().map(|w| (w.line, w.column)).collect()
+        };
+
+        let (data, warnings) = read("1 2\n2 x\n\n3 1 4\n3 1\n", Format::EdgeList);
+        assert_eq!(data.edges.len(), 2);
+        assert_eq!(positions(&warnings), vec![(2, 3), (4, 5)]);
+
+        let (data, warnings) = read("1\t2,5\n2\t1,5\t3\n3\t2,x\n", Format::WeightedAdjacency);
+        assert_eq!(data.edges.len(), 1);
+        assert_eq!(positions(&warnings), vec![(2, 7), (3, 5)]);
+
+        let (data, warnings) = read("p sp 2 2\na 1 2 4\na 1 9 1\nq\n", Format::Dimacs);
+        assert_eq!(data.edges.len(), 1);
+        assert_eq!(positions(&warnings), vec![(3, 5), (4, 1)]);
+
+        // One bad statement per line, or up to its `;`
+        let text = "digraph {\n  1 -> x -> 2\n  1 -> 2 [weight=w]; 2 -> 3\n  3 -> 1\n}";
+        let (data, warnings) = read(text, Format::Dot);
+        let edges: Vec<(usize, usize)> = data.edges.iter().map(|e| (e.from, e.to)).collect();
+        assert_eq!(edges, vec![(2, 3), (3, 1)]);
+        assert_eq!(positions(&warnings), vec![(2, 8), (3, 18)]);
+        // Structure is still an error
+        let e = read_text::<usize>(
+            "digraph { 1 -> 2".to_string(),
+            file,
+            Format::Dot,
+            Mode::Lenient,
+        )
+        .unwrap_err();
+        assert_eq!(e.to_string(), "g: missing `}`, found the end of the file");
+
+        let text = "<graphml><graph>\n<node id=\"1\"/><node id=\"x\"/>\n\
+                    <edge source=\"1\" target=\"2\"><data key=\"weight\">z</data></edge>\n\
+                    <edge source=\"2\" target=\"1\"/></graph></graphml>";
+        let (data, warnings) = read(text, Format::GraphMl);
+        assert_eq!(data.nodes, vec![1, 2]);
+        assert_eq!(data.edges.len(), 1);
+        assert_eq!(warnings[0].token, "x");
+        assert_eq!(warnings[1].token, "z");
+        assert_eq!(positions(&warnings), vec![(2, 15), (3, 48)]);
+    }
+}
@@ -0,0 +1,376 @@
+// Line-based graph files: where they stop making sense, and what to do about it. Every loader
+// reads through `Records`, which skips blank lines and either stops at the first bad line or
+// steps over it with a warning.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// What a loader does with a line it can't read.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    // Give up with that line's error
+    #[default]
+    Strict,
+    // Skip it and keep going, collecting the error as a warning
+    Lenient,
+}
+
+/// A line of a graph file that doesn't parse. Lines and columns count from 1, columns in
+/// characters; `token` is what was found there, empty at the end of the line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(
+        file: &Path,
+        line: usize,
+        column: usize,
+        token: &str,
+        message: impl Into<String>,
+    ) -> Self {
+        ParseError {
+            file: file.to_path_buf(),
+            line,
+            column,
+            token: token.to_string(),
+            message: message.into(),
+        }
+    }
+
+    // About no line in particular, like a header that never came: line and column are 0
+    pub fn whole_file(file: &Path, message: impl Into<String>) -> Self {
+        ParseError::new(file, 0, 0, "", message)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}: {}", self.file.display(), self.message);
+        }
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.file.display(),
+            self.line,
+            self.column,
+            self.message
+        )?;
+        if self.token.is_empty() {
+            write!(f, ", found end of line")
+        } else {
+            write!(f, ", found {:?}", self.token)
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io { file: PathBuf, error: io::Error },
+    Parse(ParseError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io { file, error } => write!(f, "{}: {}", file.display(), error),
+            LoadError::Parse(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io { error, .. } => Some(error),
+            LoadError::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<ParseError> for LoadError {
+    fn from(e: ParseError) -> Self {
+        LoadError::Parse(e)
+    }
+}
+
+/// A word of a line, and the column it starts at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub column: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Token<'a> {
+    // "3,10" into "3" and "10", each with its own column
+    pub fn split_once(&self, delimiter: char) -> Option<(Token<'a>, Token<'a>)> {
+        let (left, right) = self.text.split_once(delimiter)?;
+        let right = Token {
+            column: self.column + left.chars().count() + 1,
+            text: right,
+        };
+        let left = Token {
+            column: self.column,
+            text: left,
+        };
+        Some((left, right))
+    }
+}
+
+/// One non-blank line of a file, for a `Records` parser to take apart.
+#[derive(Debug, Clone, Copy)]
+pub struct Line<'a> {
+    file: &'a Path,
+    pub number: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    // Separated by whitespace, so a trailing tab is no problem
+    pub fn tokens(&self) -> impl Iterator<Item = Token<'a>> {
+        let mut rest = self.text;
+        let mut column = 1;
+        std::iter::from_fn(move || {
+            let start = rest.find(|c: char| !c.is_whitespace())?;
+            column += rest[..start].chars().count();
+            rest = &rest[start..];
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let token = Token {
+                column,
+                text: &rest[..end],
+            };
+            column += rest[..end].chars().count();
+            rest = &rest[end..];
+            Some(token)
+        })
+    }
+
+    // At `token`, or at the end of the line if there's none
+    pub fn error(&self, token: Option<Token<'_>>, message: impl Into<String>) -> ParseError {
+        match token {
+            Some(token) => {
+                ParseError::new(self.file, self.number, token.column, token.text, message)
+            }
+            None => {
+                let end = self.text.chars().count() + 1;
+                ParseError::new(self.file, self.number, end, "", message)
+            }
+        }
+    }
+
+    // `what` is for the message: "expected a node id, found ..."
+    pub fn parse<T: FromStr>(&self, token: Option<Token<'_>>, what: &str) -> Result<T, ParseError> {
+        token
+            .and_then(|t| t.text.parse().ok())
+            .ok_or_else(|| self.error(token, format!("expected {}", what)))
+    }
+
+    // Everything has been read; anything more is an error
+    pub fn end(&self, mut tokens: impl Iterator<Item = Token<'a>>) -> Result<(), ParseError> {
+        match tokens.next() {
+            Some(extra) => Err(self.error(Some(extra), "expected the end of the line")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// What `parse` makes of each non-blank line of a file, streamed so that big files don't
+/// have to fit in memory twice. A bad line ends the iteration in strict mode and is skipped
+/// in lenient mode; either way `finish`, called once the iteration is over, tells which.
+pub struct Records<R> {
+    file: PathBuf,
+    lines: io::Lines<Box<dyn BufRead>>,
+    number: usize,
+    mode: Mode,
+    parse: fn(&Line<'_>) -> Result<R, ParseError>,
+    warnings: Vec<ParseError>,
+    failed: Option<LoadError>,
+}
+
+impl<R> Records<R> {
+    pub fn open(
+        path: impl AsRef<Path>,
+        mode: Mode,
+        parse: fn(&Line<'_>) -> Result<R, ParseError>,
+    ) -> Result<Self, LoadError> {
+        let file = path.as_ref();
+        match File::open(file) {
+            Ok(f) => Ok(Records::new(file, BufReader::new(f), mode, parse)),
+            Err(error) => Err(LoadError::Io {
+                file: file.to_path_buf(),
+                error,
+            }),
+        }
+    }
+
+    // Lines from anywhere, text already in memory say; `file` is what errors call it
+    pub fn new(
+        file: &Path,
+        reader: impl BufRead + 'static,
+        mode: Mode,
+        parse: fn(&Line<'_>) -> Result<R, ParseError>,
+    ) -> Self {
+        let reader: Box<dyn BufRead> = Box::new(reader);
+        Records {
+            file: file.to_path_buf(),
+            lines: reader.lines(),
+            number: 0,
+            mode,
+            parse,
+            warnings: Vec::new(),
+            failed: None,
+        }
+    }
+
+    // The error that stopped the iteration, or the lines that were skipped
+    pub fn finish(self) -> Result<Vec<ParseError>, LoadError> {
+        match self.failed {
+            Some(e) => Err(e),
+            None => Ok(self.warnings),
+        }
+    }
+
+    // All of them at once, for files that are small anyway
+    pub fn read_all(mut self) -> Result<(Vec<R>, Vec<ParseError>), LoadError> {
+        let records = self.by_ref().collect();
+        Ok((records, self.finish()?))
+    }
+}
+
+impl<R> Iterator for Records<R> {
+    type Item = R;
+
+    fn next(&mut self) -> Option<R> {
+        if self.failed.is_some() {
+            return None;
+        }
+        loop {
+            let text = match self.lines.next()? {
+                Ok(text) => text,
+                Err(error) => {
+                    self.failed = Some(LoadError::Io {
+                        file: self.file.clone(),
+                        error,
+                    });
+                    return None;
+                }
+            };
+            self.number += 1;
+            if text.trim().is_empty() {
+                continue;
+            }
+            let line = Line {
+                file: &self.file,
+                number: self.number,
+                text: &text,
+            };
+            match (self.parse)(&line) {
+                Ok(record) => return Some(record),
+                Err(e) if self.mode == Mode::Lenient => self.warnings.push(e),
+                Err(e) => {
+                    self.failed = Some(e.into());
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+// For entry points: what was skipped, on stderr, without drowning everything else
+pub fn report(warnings: &[ParseError]) {
+    const SHOWN: usize = 10;
+    for warning in warnings.iter().take(SHOWN) {
+        eprintln!("warning: skipped {}", warning);
+    }
+    if warnings.len() > SHOWN {
+        eprintln!("warning: skipped {} more lines", warnings.len() - SHOWN);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TempFile;
+
+    fn pair(line: &Line<'_>) -> Result<(u32, u32), ParseError> {
+        let mut tokens = line.tokens();
+        let a = line.parse(tokens.next(), "a number")?;
+        let b = line.parse(tokens.next(), "a number")?;
+        line.end(tokens)?;
+        Ok((a, b))
+    }
+
+    #[test]
+    fn tokens_know_their_columns() {
+        let file = PathBuf::from("x");
+        let line = Line {
+            file: &file,
+            number: 1,
+            text: "  ab\tcdé  f\t",
+        };
+        let tokens: Vec<(usize, &str)> = line.tokens().map(|t| (t.column, t.text)).collect();
+        assert_eq!(tokens, vec![(3, "ab"), (6, "cdé"), (11, "f")]);
+        let (left, right) = Token {
+            column: 4,
+            text: "12,x",
+        }
+        .split_once(',')
+        .unwrap();
+        assert_eq!((left.column, right.column, right.text), (4, 7, "x"));
+        let error = line.error(None, "expected more");
+        assert_eq!(error.column, 13);
+        assert_eq!(
+            error.to_string(),
+            "x:1:13: expected more, found end of line"
+        );
+        let error = ParseError::whole_file(&file, "no header");
+        assert_eq!(error.to_string(), "x: no header");
+    }
+
+    #[test]
+    fn strict_stops_and_lenient_skips() {
+        let file = TempFile::new("parse-pairs", "1 2\n\n3 x\n4\n5 6 7\n8 9\n");
+        let path = file.path.clone();
+        let e = Records::open(&path, Mode::Strict, pair)
+            .unwrap()
+            .read_all()
+            .unwrap_err();
+        match e {
+            LoadError::Parse(e) => {
+                assert_eq!((e.line, e.column, e.token.as_str()), (3, 3, "x"));
+                assert!(e
+                    .to_string()
+                    .ends_with(":3:3: expected a number, found \"x\""));
+            }
+            e => panic!("{}", e),
+        }
+
+        let (pairs, warnings) = Records::open(&path, Mode::Lenient, pair)
+            .unwrap()
+            .read_all()
+            .unwrap();
+        drop(file);
+        assert_eq!(pairs, vec![(1, 2), (8, 9)]);
+        let found: Vec<(usize, usize, &str)> = warnings
+            .iter()
+            .map(|w| (w.line, w.column, w.token.as_str()))
+            .collect();
+        assert_eq!(found, vec![(3, 3, "x"), (4, 2, ""), (5, 5, "7")]);
+
+        match Records::open(&path, Mode::Strict, pair) {
+            Err(LoadError::Io { file, .. }) => assert_eq!(file, path),
+            _ => panic!("the file is gone"),
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use super::scc::{Components, Condensation};
+use super::Adjacency;
+use std::fmt;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// How `Reachability` remembers which components of the condensation reach which.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    // The whole transitive closure, a bit per pair of components. One lookup per query, but
+    // k^2 / 8 bytes, so only for small DAGs.
+    Bitset,
+    // Tree-cover labels: a DFS forest of the DAG numbers components in postorder, so every
+    // subtree is an interval of numbers, and each component keeps the merged intervals of
+    // everything it reaches. A query is a binary search through those.
+    Intervals,
+}
+
+impl Strategy {
+    // Up to this many components the closure takes at most 8 MiB
+    pub const BITSET_LIMIT: usize = 1 << 13;
+    // and even when asked for, it's not built past 512 MiB
+    pub const BITSET_MAX: usize = 1 << 16;
+
+    pub fn for_components(count: usize) -> Self {
+        if count <= Strategy::BITSET_LIMIT {
+            Strategy::Bitset
+        } else {
+            Strategy::Intervals
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Strategy::Bitset => "bitset",
+            Strategy::Intervals => "intervals",
+        })
+    }
+}
+
+impl std::str::FromStr for Strategy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "bitset" => Ok(Strategy::Bitset),
+            "intervals" => Ok(Strategy::Intervals),
+            _ => Err(format!("unknown reachability strategy {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Labels {
+    // Row `c` of `words` words has bit `d` set if c reaches d
+    Bitset {
+        words: usize,
+        rows: Vec<u64>,
+    },
+    // Component `c` reaches `d` if post[d] is in one of intervals[offsets[c]..offsets[c + 1]],
+    // which are sorted and don't touch
+    Intervals {
+        post: Vec<u32>,
+        offsets: Vec<usize>,
+        intervals: Vec<(u32, u32)>,
+    },
+}
+
+/// Answers "can `from` reach `to`?" without searching. Nodes of one strongly connected
+/// component reach each other, so the labels are built on the condensation, which is
+/// usually much smaller than the graph.
+#[derive(Debug, Clone)]
+pub struct Reachability<Id: Clone + Eq + Hash> {
+    pub components: Components<Id>,
+    pub dag_edges: usize,
+    // Condensing included
+    pub build_time: Duration,
+    labels: Labels,
+}
+
+impl<Id: Clone + Eq + Hash> Reachability<Id> {
+    // Bitset for up to BITSET_LIMIT components, intervals above that
+    pub fn new<G: Adjacency<Id = Id> + ?Sized>(graph: &G) -> Self {
+        Self::build(graph, None).unwrap()
+    }
+
+    // Errs if asked for a bitset over more than BITSET_MAX components
+    pub fn with_strategy<G: Adjacency<Id = Id> + ?Sized>(
+        graph: &G,
+        strategy: Strategy,
+    ) -> Result<Self, String> {
+        Self::build(graph, Some(strategy))
+    }
+
+    fn build<G: Adjacency<Id = Id> + ?Sized>(
+        graph: &G,
+        strategy: Option<Strategy>,
+    ) -> Result<Self, String> {
+        let start = Instant::now();
+        let condensation = Condensation::new(graph);
+        let k = condensation.components.count();
+        let strategy = strategy.unwrap_or_else(|| Strategy::for_components(k));
+        if strategy == Strategy::Bitset && k > Strategy::BITSET_MAX {
+            return Err(format!(
+                "{} components are too many for a bitset, the most is {}",
+                k,
+                Strategy::BITSET_MAX
+            ));
+        }
+        // Tarjan numbers components sinks first, so every DAG edge goes to a lower number
+        let successors: Vec<Vec<usize>> = (0..k)
+            .map(|c| condensation.dag.nodes[&c].edges.clone())
+            .collect();
+        let labels = match strategy {
+            Strategy::Bitset => bitset_closure(&successors),
+            Strategy::Intervals => interval_labels(&successors),
+        };
+        Ok(Reachability {
+            components: condensation.components,
+            dag_edges: condensation.edge_counts.len(),
+            build_time: start.elapsed(),
+            labels,
+        })
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        match self.labels {
+            Labels::Bitset { .. } => Strategy::Bitset,
+            Labels::Intervals { .. } => Strategy::Intervals,
+        }
+    }
+
+    // Every node reaches itself. Nodes that aren't in the graph reach nothing.
+    pub fn reaches(&self, from: &Id, to: &Id) -> bool {
+        match (self.components.of.get(from), self.components.of.get(to)) {
+            (Some(&a), Some(&b)) => self.component_reaches(a, b),
+            _ => false,
+        }
+    }
+
+    pub fn component_reaches(&self, a: usize, b: usize) -> bool {
+        match &self.labels {
+            Labels::Bitset { words, rows } => rows[a * words + b / 64] >> (b % 64) & 1 == 1,
+            Labels::Intervals {
+                post,
+                offsets,
+                intervals,
+            } => {
+                let p = post[b];
+                let label = &intervals[offsets[a]..offsets[a + 1]];
+                let i = label.partition_point(|&(low, _)| low <= p);
+                i > 0 && label[i - 1].1 >= p
+            }
+        }
+    }
+
+    // Labels plus the component of every node, roughly
+    pub fn size_bytes(&self) -> usize {
+        use std::mem::size_of;
+        let labels = match &self.labels {
+            Labels::Bitset { rows, .. } => rows.len() * size_of::<u64>(),
+            Labels::Intervals {
+                post,
+                offsets,
+                intervals,
+            } => {
+                post.len() * size_of::<u32>()
+                    + offsets.len() * size_of::<usize>()
+                    + intervals.len() * size_of::<(u32, u32)>()
+            }
+        };
+        labels + self.components.of.len() * size_of::<(Id, usize)>()
+    }
+}
+
+// Sinks first, each row is its own bit or'ed with the rows of its successors, all done by then
+fn bitset_closure(successors: &[Vec<usize>]) -> Labels {
+    let k = successors.len();
+    let words = k.div_ceil(64);
+    let mut rows = vec![0u64; k * words];
+    for (c, next) in successors.iter().enumerate() {
+        let (done, rest) = rows.split_at_mut(c * words);
+        let row = &mut rest[..words];
+        row[c / 64] |= 1 << (c % 64);
+        for &d in next {
+            debug_assert!(d < c);
+            for (word, other) in row.iter_mut().zip(&done[d * words..(d + 1) * words]) {
+                *word |= other;
+            }
+        }
+    }
+    Labels::Bitset { words, rows }
+}
+
+// One DFS over the DAG. Postorder numbers handed out while a component is open all go to its
+// subtree, so the subtree is [number when it was discovered, its own number]. There are no
+// back edges in a DAG, so by the time a component finishes all of its successors have, and
+// their labels cover everything it reaches outside the subtree.
+fn interval_labels(successors: &[Vec<usize>]) -> Labels {
+    let k = successors.len();
+    let mut post = vec![u32::MAX; k];
+    let mut low = vec![0u32; k];
+    let mut discovered = vec![false; k];
+    let mut labels: Vec<Vec<(u32, u32)>> = vec![Vec::new(); k];
+    let mut counter = 0u32;
+    let mut work: Vec<(usize, usize)> = Vec::new();
+    // Edges only go down, so going from the top starts trees at sources: few, deep ones
+    for root in (0..k).rev() {
+        if discovered[root] {
+            continue;
+        }
+        discovered[root] = true;
+        low[root] = counter;
+        work.push((root, 0));
+        while let Some((c, next)) = work.last_mut() {
+            let c = *c;
+            if let Some(&d) = successors[c].get(*next) {
+                *next += 1;
+                if !discovered[d] {
+                    discovered[d] = true;
+                    low[d] = counter;
+                    work.push((d, 0));
+                }
+                continue;
+            }
+            work.pop();
+            post[c] = counter;
+            counter += 1;
+            let mut label = vec![(low[c], post[c])];
+            for &d in &successors[c] {
+                label.extend_from_slice(&labels[d]);
+            }
+            labels[c] = merge(label);
+        }
+    }
+    let mut offsets = Vec::with_capacity(k + 1);
+    let mut intervals = Vec::new();
+    offsets.push(0);
+    for label in labels {
+        intervals.extend(label);
+        offsets.push(intervals.len());
+    }
+    Labels::Intervals {
+        post,
+        offsets,
+        intervals,
+    }
+}
+
+// Sorted, with overlapping and adjacent intervals joined
+fn merge(mut intervals: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    intervals.sort_unstable();
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(intervals.len());
+    for (low, high) in intervals {
+        match merged.last_mut() {
+            Some(last) if low <= last.1.saturating_add(1) => last.1 = last.1.max(high),
+            _ => merged.push((low, high)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::bfs::bfs;
+    use crate::graph::{Forward, Graph};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn both(graph: &Graph<u32>) -> [Reachability<u32>; 2] {
+        [
+            Reachability::with_strategy(graph, Strategy::Bitset).unwrap(),
+            Reachability::with_strategy(graph, Strategy::Intervals).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn small_graph() {
+        // A cycle 1 -> 2 -> 3 -> 1 feeding 4 -> 5, with 6 -> 5 off to the side
+        let mut graph: Graph<u32> = Graph::new();
+        for (from, to) in [(1, 2), (2, 3), (3, 1), (3, 4), (4, 5), (6, 5)] {
+            graph.add_edge(from, to);
+        }
+        for index in both(&graph) {
+            assert_eq!(index.components.count(), 4);
+            assert_eq!(index.dag_edges, 3);
+            assert!(index.reaches(&3, &2) && index.reaches(&1, &5) && index.reaches(&6, &5));
+            assert!(!index.reaches(&5, &4) && !index.reaches(&6, &1) && !index.reaches(&4, &6));
+            assert!(index.reaches(&4, &4));
+            assert!(!index.reaches(&1, &7));
+            assert!(index.size_bytes() > 0);
+        }
+        assert_eq!(Reachability::new(&graph).strategy(), Strategy::Bitset);
+    }
+
+    #[test]
+    fn merges_touching_intervals() {
+        assert_eq!(
+            merge(vec![(5, 6), (0, 2), (3, 3), (8, 9), (1, 4)]),
+            vec![(0, 6), (8, 9)]
+        );
+    }
+
+    #[test]
+    fn agrees_with_bfs() {
+        let mut rng = StdRng::seed_from_u64(22);
+        for _ in 0..30 {
+            let n = rng.gen_range(1..40u32);
+            let m = rng.gen_range(0..3 * n);
+            let mut graph: Graph<u32> = Graph::new();
+            for v in 0..n {
+                graph.add_node(&v);
+            }
+            for _ in 0..m {
+                graph.add_edge(rng.gen_range(0..n), rng.gen_range(0..n));
+            }
+            let indexes = both(&graph);
+            for from in 0..n {
+                let reached = bfs(&graph, Some(from), Forward);
+                for to in 0..n {
+                    for index in &indexes {
+                        assert_eq!(index.reaches(&from, &to), reached.reached(&to));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn strategies_agree_on_a_generated_graph() {
+        let mut graph: Graph<u32> = Graph::new();
+        for (from, to) in crate::generate::scc_edges(3000, &[200, 40, 10], 4500, 22) {
+            graph.add_edge(from as u32, to as u32);
+        }
+        let [bits, intervals] = both(&graph);
+        let ids = graph.node_ids();
+        let mut rng = StdRng::seed_from_u64(22);
+        for _ in 0..20_000 {
+            let from = &ids[rng.gen_range(0..ids.len())];
+            let to = &ids[rng.gen_range(0..ids.len())];
+            assert_eq!(bits.reaches(from, to), intervals.reaches(from, to));
+        }
+        // Everything one node reaches, checked against a search
+        let reached = bfs(&graph, Some(ids[0]), Forward);
+        for to in &ids {
+            assert_eq!(intervals.reaches(&ids[0], to), reached.reached(to));
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use super::{Adjacency, Graph};
+use fxhash::FxHashMap as HashMap;
+use fxhash::FxHashSet as HashSet;
+
+/// Strongly connected components as a component id per node. Ids are `0..sizes.len()`, in the
+/// order Tarjan finishes the components, which is a reverse topological order of the
+/// condensation: nothing in component `c` has an edge into a component numbered above `c`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Components<Id: Eq + std::hash::Hash> {
+    pub of: HashMap<Id, usize>,
+    pub sizes: Vec<usize>,
+}
+
+impl<Id: Clone + Eq + std::hash::Hash> Components<Id> {
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+
+    // Nodes of each component, in no particular order within one
+    pub fn members(&self) -> Vec<Vec<Id>> {
+        let mut members = vec![Vec::new(); self.count()];
+        for (id, &c) in &self.of {
+            members[c].push(id.clone());
+        }
+        members
+    }
+}
+
+// Tarjan's algorithm with an explicit stack of (node, next edge to look at), so that SCC.txt's
+// long paths don't overflow the call stack. One pass over the forward edges only.
+pub fn tarjan<G: Adjacency + ?Sized>(graph: &G) -> Components<G::Id> {
+    // Discovery index of each node; `low` and `on_stack` are indexed by it
+    let mut index: HashMap<G::Id, usize> = HashMap::default();
+    index.reserve(graph.node_count());
+    let mut low: Vec<usize> = Vec::with_capacity(graph.node_count());
+    let mut on_stack: Vec<bool> = Vec::with_capacity(graph.node_count());
+    let mut stack: Vec<G::Id> = Vec::new();
+    let mut work: Vec<(G::Id, usize)> = Vec::new();
+    let mut components = Components {
+        of: HashMap::default(),
+        sizes: Vec::new(),
+    };
+
+    for root in graph.node_ids() {
+        if index.contains_key(&root) {
+            continue;
+        }
+        index.insert(root.clone(), low.len());
+        low.push(low.len());
+        on_stack.push(true);
+        stack.push(root.clone());
+        work.push((root, 0));
+
+        while let Some((node, next)) = work.last_mut() {
+            let v = index[&*node];
+            let edges = graph.out_edges(node);
+            if *next < edges.len() {
+                let to = &edges[*next];
+                *next += 1;
+                match index.get(to) {
+                    None => {
+                        index.insert(to.clone(), low.len());
+                        low.push(low.len());
+                        on_stack.push(true);
+                        stack.push(to.clone());
+                        work.push((to.clone(), 0));
+                    }
+                    Some(&w) if on_stack[w] => low[v] = low[v].min(w),
+                    Some(_) => {}
+                }
+                continue;
+            }
+            // All edges done: `node` is the root of a component if nothing below reached higher
+            let node = work.pop().unwrap().0;
+            if low[v] == v {
+                let c = components.sizes.len();
+                let mut size = 0;
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[index[&member]] = false;
+                    size += 1;
+                    let done = member == node;
+                    components.of.insert(member, c);
+                    if done {
+                        break;
+                    }
+                }
+                components.sizes.push(size);
+            }
+            if let Some((parent, _)) = work.last() {
+                let p = index[parent];
+                low[p] = low[p].min(low[v]);
+            }
+        }
+    }
+    components
+}
+
+/// One node per strongly connected component (numbered as in `components`), an edge between
+/// two of them if any edge of the original graph goes across, once however many do.
+#[derive(Debug)]
+pub struct Condensation<Id: Clone + Eq + std::hash::Hash> {
+    pub components: Components<Id>,
+    pub dag: Graph<usize>,
+    // Original edges behind each DAG edge
+    pub edge_counts: HashMap<(usize, usize), usize>,
+}
+
+impl<Id: Clone + Eq + std::hash::Hash> Condensation<Id> {
+    pub fn new<G: Adjacency<Id = Id> + ?Sized>(graph: &G) -> Self {
+        Self::from_components(graph, tarjan(graph))
+    }
+
+    // Works with any labelling, e.g. one from kosaraju
+    pub fn from_components<G: Adjacency<Id = Id> + ?Sized>(
+        graph: &G,
+        components: Components<Id>,
+    ) -> Self {
+        let mut dag = Graph::new();
+        for c in 0..components.count() {
+            dag.add_node(&c);
+        }
+        let mut edge_counts: HashMap<(usize, usize), usize> = HashMap::default();
+        for from in graph.node_ids() {
+            let a = components.of[&from];
+            for to in graph.out_edges(&from) {
+                let b = components.of[to];
+                if a != b {
+                    let count = edge_counts.entry((a, b)).or_default();
+                    if *count == 0 {
+                        dag.add_edge(a, b);
+                    }
+                    *count += 1;
+                }
+            }
+        }
+        Condensation {
+            components,
+            dag,
+            edge_counts,
+        }
+    }
+
+    pub fn size(&self, c: usize) -> usize {
+        self.components.sizes[c]
+    }
+
+    // Components nothing else points into
+    pub fn sources(&self) -> Vec<usize> {
+        let mut sources: Vec<usize> = self
+            .dag
+            .nodes
+            .values()
+            .filter(|n| n.inverse_edges.is_empty())
+            .map(|n| n.id)
+            .collect();
+        sources.sort_unstable();
+        sources
+    }
+}
+
+// Kosaraju returns each component as a node-only graph; number them the same way
+pub fn components_from_graphs<Id: Clone + Eq + std::hash::Hash>(
+    sccs: &[Graph<Id>],
+) -> Components<Id> {
+    let mut components = Components {
+        of: HashMap::default(),
+        sizes: Vec::new(),
+    };
+    for scc in sccs.iter().filter(|scc| !scc.nodes.is_empty()) {
+        let c = components.sizes.len();
+        components.sizes.push(scc.nodes.len());
+        for id in scc.nodes.keys() {
+            components.of.insert(id.clone(), c);
+        }
+    }
+    components
+}
+
+// Whether two labellings put the same nodes together, whatever the numbers
+pub fn same_partition<Id: Clone + Eq + std::hash::Hash>(
+    a: &Components<Id>,
+    b: &Components<Id>,
+) -> bool {
+    if a.of.len() != b.of.len() || a.count() != b.count() {
+        return false;
+    }
+    let mut pairs = HashSet::default();
+    for (id, &x) in &a.of {
+        match b.of.get(id) {
+            Some(&y) => {
+                pairs.insert((x, y));
+            }
+            None => return false,
+        }
+    }
+    // A bijection between the ids: one pair per component, and every id of `b` used
+    let used: HashSet<usize> = pairs.iter().map(|&(_, y)| y).collect();
+    pairs.len() == a.count() && used.len() == b.count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::csr::CsrGraph;
+
+    #[test]
+    fn components_come_out_sinks_first() {
+        // 1 <-> 2 -> 3 <-> 4 -> 5, and 6 on its own
+        let mut graph: Graph<usize> = Graph::new();
+        for (from, to) in [(1, 2), (2, 1), (2, 3), (1, 3), (3, 4), (4, 3), (4, 5)] {
+            graph.add_edge(from, to);
+        }
+        graph.add_node(&6);
+        let components = tarjan(&graph);
+        assert_eq!(components.count(), 4);
+        let c = |id: usize| components.of[&id];
+        assert_eq!(c(1), c(2));
+        assert_eq!(c(3), c(4));
+        assert!(c(5) < c(3) && c(3) < c(1));
+
+        let condensation = Condensation::new(&graph);
+        assert_eq!(condensation.dag.nodes.len(), 4);
+        assert_eq!(condensation.dag.nodes[&c(1)].edges, vec![c(3)]);
+        assert_eq!(condensation.edge_counts[&(c(1), c(3))], 2);
+        assert_eq!(condensation.size(c(3)), 2);
+        let mut sources = vec![c(1), c(6)];
+        sources.sort_unstable();
+        assert_eq!(condensation.sources(), sources);
+        for node in condensation.dag.nodes.values() {
+            for &to in &node.edges {
+                assert!(to < node.id);
+            }
+        }
+    }
+
+    #[test]
+    fn deep_paths_dont_overflow() {
+        // A 200,000 node cycle: one component, found without recursion
+        let n = 200_000u32;
+        let csr = CsrGraph::from_edges(0..n, (0..n).map(|i| (i, (i + 1) % n)));
+        assert_eq!(tarjan(&csr).sizes, vec![n as usize]);
+        let path = CsrGraph::from_edges(0..n, (0..n - 1).map(|i| (i, i + 1)));
+        let condensation = Condensation::new(&path);
+        assert_eq!(condensation.components.count(), n as usize);
+        assert_eq!(condensation.edge_counts.len(), n as usize - 1);
+    }
+
+    #[test]
+    fn partitions_compare_by_membership() {
+        let mut graph: Graph<&str> = Graph::new();
+        graph.add_edge("a", "b");
+        graph.add_edge("b", "a");
+        graph.add_edge("b", "c");
+        let components = tarjan(&graph);
+        let mut swapped = components.clone();
+        for c in swapped.of.values_mut() {
+            *c = 1 - *c;
+        }
+        swapped.sizes.reverse();
+        assert!(same_partition(&components, &swapped));
+        let mut merged = components.clone();
+        merged.of.insert("c", components.of["a"]);
+        assert!(!same_partition(&components, &merged));
+        assert_eq!(components.members().iter().map(Vec::len).sum::<usize>(), 3);
+    }
+}
@@ -0,0 +1,231 @@
+use super::Adjacency;
+use fxhash::FxHashMap as HashMap;
+use fxhash::FxHashSet as HashSet;
+use std::collections::VecDeque;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    // Reverse finish order of a depth first search
+    Dfs,
+    // Repeatedly take a node nothing unsorted points into
+    Kahn,
+}
+
+/// Why there is no topological order: `nodes[0] -> nodes[1] -> ... -> nodes[0]`. A self loop
+/// is a cycle of one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<T> {
+    pub nodes: Vec<T>,
+}
+
+impl<T: fmt::Debug> fmt::Display for Cycle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cycle: ")?;
+        for node in &self.nodes {
+            write!(f, "{:?} -> ", node)?;
+        }
+        match self.nodes.first() {
+            Some(first) => write!(f, "{:?}", first),
+            None => write!(f, "(empty)"),
+        }
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for Cycle<T> {}
+
+// Every edge goes from earlier to later in the returned order.
+pub fn toposort<G: Adjacency + ?Sized>(
+    graph: &G,
+    method: Method,
+) -> Result<Vec<G::Id>, Cycle<G::Id>> {
+    match method {
+        Method::Dfs => toposort_dfs(graph),
+        Method::Kahn => toposort_kahn(graph),
+    }
+}
+
+pub fn toposort_dfs<G: Adjacency + ?Sized>(graph: &G) -> Result<Vec<G::Id>, Cycle<G::Id>> {
+    // Nodes on the current path are in `work`, finished ones in `done`
+    let mut on_path: HashSet<G::Id> = HashSet::default();
+    let mut done: HashSet<G::Id> = HashSet::default();
+    let mut finished = Vec::with_capacity(graph.node_count());
+    let mut work: Vec<(G::Id, usize)> = Vec::new();
+    for root in graph.node_ids() {
+        if done.contains(&root) {
+            continue;
+        }
+        on_path.insert(root.clone());
+        work.push((root, 0));
+        while let Some((node, next)) = work.last_mut() {
+            let edges = graph.out_edges(node);
+            if *next < edges.len() {
+                let to = &edges[*next];
+                *next += 1;
+                if on_path.contains(to) {
+                    // Back edge: the cycle is the path from `to` down to here
+                    let start = work.iter().rposition(|(n, _)| n == to).unwrap();
+                    return Err(Cycle {
+                        nodes: work.drain(start..).map(|(n, _)| n).collect(),
+                    });
+                }
+                if !done.contains(to) {
+                    on_path.insert(to.clone());
+                    work.push((to.clone(), 0));
+                }
+                continue;
+            }
+            let node = work.pop().unwrap().0;
+            on_path.remove(&node);
+            done.insert(node.clone());
+            finished.push(node);
+        }
+    }
+    finished.reverse();
+    Ok(finished)
+}
+
+pub fn toposort_kahn<G: Adjacency + ?Sized>(graph: &G) -> Result<Vec<G::Id>, Cycle<G::Id>> {
+    let ids = graph.node_ids();
+    let mut in_degree: HashMap<G::Id, usize> = HashMap::default();
+    let mut ready = VecDeque::new();
+    for id in &ids {
+        let degree = graph.in_edges(id).len();
+        if degree == 0 {
+            ready.push_back(id.clone());
+        }
+        in_degree.insert(id.clone(), degree);
+    }
+    let mut order = Vec::with_capacity(ids.len());
+    while let Some(node) = ready.pop_front() {
+        for to in graph.out_edges(&node) {
+            let degree = in_degree.get_mut(to).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                ready.push_back(to.clone());
+            }
+        }
+        order.push(node);
+    }
+    if order.len() == ids.len() {
+        return Ok(order);
+    }
+
+    // Whatever is left has an unsorted predecessor, so walking backwards through those must
+    // come around to a node seen before.
+    let left = |id: &G::Id| in_degree[id] > 0;
+    let mut node = ids.into_iter().find(|id| left(id)).unwrap();
+    let mut path = Vec::new();
+    let mut position: HashMap<G::Id, usize> = HashMap::default();
+    while !position.contains_key(&node) {
+        position.insert(node.clone(), path.len());
+        path.push(node.clone());
+        node = graph
+            .in_edges(&node)
+            .iter()
+            .find(|id| left(id))
+            .unwrap()
+            .clone();
+    }
+    let mut nodes = path.split_off(position[&node]);
+    // The walk went against the edges
+    nodes.reverse();
+    Err(Cycle { nodes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+
+    fn assert_is_order(graph: &Graph<usize>, order: &[usize]) {
+        assert_eq!(order.len(), graph.nodes.len());
+        let position: HashMap<usize, usize> =
+            order.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+        for node in graph.nodes.values() {
+            for to in &node.edges {
+                assert!(
+                    position[&node.id] < position[to],
+                    "{} -> {} in {:?}",
+                    node.id,
+                    to,
+                    order
+                );
+            }
+        }
+    }
+
+    fn assert_is_cycle(graph: &Graph<usize>, cycle: &Cycle<usize>) {
+        assert!(!cycle.nodes.is_empty());
+        let n = cycle.nodes.len();
+        for i in 0..n {
+            let (from, to) = (cycle.nodes[i], cycle.nodes[(i + 1) % n]);
+            assert!(
+                graph.nodes[&from].edges.contains(&to),
+                "no edge {} -> {}",
+                from,
+                to
+            );
+        }
+        let distinct: HashSet<&usize> = cycle.nodes.iter().collect();
+        assert_eq!(distinct.len(), n);
+    }
+
+    #[test]
+    fn sorts_dags() {
+        // Diamond 1 -> {2, 3} -> 4, a tail 4 -> 5 and a lone 6; doubled edges are fine
+        let mut graph: Graph<usize> = Graph::new();
+        for (from, to) in [(1, 2), (1, 3), (3, 4), (2, 4), (4, 5), (2, 4)] {
+            graph.add_edge(from, to);
+        }
+        graph.add_node(&6);
+        for method in [Method::Dfs, Method::Kahn] {
+            let order = toposort(&graph, method).unwrap();
+            assert_is_order(&graph, &order);
+        }
+        assert_eq!(toposort(&Graph::<usize>::new(), Method::Kahn), Ok(vec![]));
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        // 1 -> 2 -> 3 -> 4 -> 2, with 5 hanging off the cycle and 0 leading into it
+        let mut graph: Graph<usize> = Graph::new();
+        for (from, to) in [(0, 1), (1, 2), (2, 3), (3, 4), (4, 2), (3, 5)] {
+            graph.add_edge(from, to);
+        }
+        for method in [Method::Dfs, Method::Kahn] {
+            let cycle = toposort(&graph, method).unwrap_err();
+            assert_is_cycle(&graph, &cycle);
+            assert_eq!(cycle.nodes.len(), 3);
+        }
+
+        let mut looped: Graph<usize> = Graph::new();
+        looped.add_edge(1, 2);
+        looped.add_edge(2, 2);
+        for method in [Method::Dfs, Method::Kahn] {
+            assert_eq!(toposort(&looped, method), Err(Cycle { nodes: vec![2] }));
+        }
+        assert_eq!(
+            Cycle {
+                nodes: vec!["a", "b"]
+            }
+            .to_string(),
+            "cycle: \"a\" -> \"b\" -> \"a\""
+        );
+    }
+
+    #[test]
+    fn deep_chains_dont_overflow() {
+        let n = 100_000;
+        let mut graph: Graph<usize> = Graph::new();
+        for i in 0..n {
+            graph.add_edge(i, i + 1);
+        }
+        let expected: Vec<usize> = (0..=n).collect();
+        assert_eq!(toposort_dfs(&graph).unwrap(), expected);
+        assert_eq!(toposort_kahn(&graph).unwrap(), expected);
+        graph.add_edge(n, 0);
+        assert_eq!(toposort_dfs(&graph).unwrap_err().nodes.len(), n + 1);
+        assert_eq!(toposort_kahn(&graph).unwrap_err().nodes.len(), n + 1);
+    }
+}
@@ -1,8 +1,15 @@
+use serde::Serialize;
 use std::cell::RefCell;
+use std::fmt;
 use std::ops::Deref;
 use std::rc::Rc;
 
-crate::entry_point!("custom_array", main);
+crate::entry_point!("custom_array", main, {
+    category: Howto,
+    description: "Fixed-size array that derefs to a slice, filled from an Rc<RefCell<_>> chain",
+    datasets: [],
+    params: [],
+});
 
 struct Person {
     name: String,
@@ -68,16 +75,33 @@ fn rsf(xs: &[u8]) -> u8 {
     }
 }
 
-pub fn main() {
+pub fn main(_args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
     let child = Person::new("Child", 12, None);
     let parent = Person::new("Parent", 35, Some(child.clone()));
     let grandparent = Person::new("Grandparent", 60, Some(parent.clone()));
 
     let mut ages = FixedSizeArray::new();
     grandparent.borrow().collect_ages(&mut ages);
-    println!("{:?}", ages); // prints: [60, 35, 12]
+    let oldest = grandparent.borrow();
+    Ok(Box::new(Ages {
+        ages: ages.to_vec(), // [60, 35, 12]
+        name: oldest.name.clone(),
+        age: rsf(&ages),
+    }))
+}
+
+#[derive(Debug, Serialize)]
+struct Ages {
+    ages: Vec<u8>,
+    name: String,
+    age: u8,
+}
 
-    println!("{} is {} years old.", grandparent.borrow().name, rsf(&ages));
+impl fmt::Display for Ages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:?}", self.ages)?;
+        write!(f, "{} is {} years old.", self.name, self.age)
+    }
 }
 
 // Tests!
@@ -1,8 +1,15 @@
+use serde::Serialize;
 use std::collections::hash_map::RawEntryMut::Occupied;
-crate::entry_point!("howto/raw_entry_api", raw_entry_api_demo);
+crate::entry_point!("howto/raw_entry_api", raw_entry_api_demo, {
+    category: Howto,
+    description: "Pop an arbitrary HashMap entry without cloning its key (nightly raw entry API)",
+    datasets: [],
+    params: [],
+});
 
 use std::{
     collections::HashMap,
+    fmt,
     hash::{BuildHasher, Hash, Hasher},
 };
 
@@ -19,12 +26,30 @@ pub fn mut_pop_map_no_clone<K: Hash, V>(m: &mut HashMap<K, V>) -> Option<(K, V)>
     }
 }
 
-pub fn raw_entry_api_demo() {
+pub fn raw_entry_api_demo(
+    _args: &crate::cli::Args,
+) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> {
     // Thanks, Alexendoo!
     let mut map = HashMap::new();
     map.insert(1, "one");
     map.insert(2, "two");
     map.insert(3, "three");
-    println!("{:?}", mut_pop_map_no_clone(&mut map));
-    println!("{:?}", map);
+    let popped = mut_pop_map_no_clone(&mut map);
+    Ok(Box::new(Popped {
+        popped,
+        remaining: map,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+struct Popped {
+    popped: Option<(i32, &'static str)>,
+    remaining: HashMap<i32, &'static str>,
+}
+
+impl fmt::Display for Popped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:?}", self.popped)?;
+        write!(f, "{:?}", self.remaining)
+    }
 }
@@ -1,8 +1,16 @@
 #![feature(hash_raw_entry)]
 
+mod bench;
+mod cli;
+mod dataset;
+mod entry;
 mod example;
+mod generate;
 mod graph;
+mod memory;
+mod runner;
 mod util;
+mod verify;
 
 #[path = "./howto/raw_entry_api.rs"]
 mod raw_entry_api;
@@ -34,6 +42,15 @@ mod aa_kosajaru;
 #[path = "./ae_hw4/ab_kosyak.rs"]
 mod ab_kosyak;
 
+#[path = "./ae_hw4/ac_hops.rs"]
+mod ac_hops;
+
+#[path = "./ae_hw4/ad_two_sat.rs"]
+mod ad_two_sat;
+
+#[path = "./ae_hw4/ae_reach.rs"]
+mod ae_reach;
+
 #[path = "./af_hw5_dijkstra/aa_dijkstra.rs"]
 mod aa_dijkstra;
 
@@ -43,24 +60,45 @@ mod aa_two_sum;
 #[path = "./ag_hw6/ab_median.rs"]
 mod ab_median;
 
+use entry::{Category, EntryPoint};
+
 #[linkme::distributed_slice]
-static ENTRY_POINTS: [(&'static str, fn())] = [..];
+static ENTRY_POINTS: [EntryPoint] = [..];
+
+// Words that main handles itself and that entry points therefore can't be called
+const COMMANDS: [&str; 9] = [
+    "list", "help", "verify", "bench", "run", "run-all", "data", "generate", "convert",
+];
 
 /**
 Entry points can be defined like this:
 ```
-crate::entry_point!("hello", hello);
-fn hello() { ... }
+crate::entry_point!("hello", hello, {
+    category: Misc,
+    description: "Say hello",
+    datasets: [],
+    params: [],
+});
+fn hello(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> { ... }
 ```
 
+`category` is one of the `entry::Category` variants, `datasets` are file names under `data/`
+and `params` are `"--flag VALUE" => "what it does"` pairs; all of it shows up in
+`cargo run list` and `cargo run help <name>`. For quick experiments the metadata can be left
+out altogether (`crate::entry_point!("hello", hello);`), which files the entry point under misc.
+
+Everything after the entry point name on the command line ends up in `args`
+(see `cli::Args` for the flags). The answer is anything `Display + Serialize`, printed as
+text or with `--format json` as JSON; an `Err` is reported by `main` with a non-zero exit code.
+
 If you define more than one entry point in one module,
 for technical reasons you need to specify unique identifier names:
 ```
 crate::entry_point!("hello1", hello1, _EP_HELLO1);
-fn hello1() { ... }
+fn hello1(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> { ... }
 
-crate::entry_point!("hello2", hello2, _EP_HELLO2);
-fn hello2() { ... }
+crate::entry_point!("hello2", hello2, _EP_HELLO2, { ... });
+fn hello2(args: &crate::cli::Args) -> Result<Box<dyn crate::entry::Answer>, crate::cli::Error> { ... }
 ```
 */
 
@@ -70,16 +108,44 @@ macro_rules! entry_point {
         $crate::entry_point!($name, $f, _ENTRY_POINT);
     };
     ($name:expr, $f:expr, $static_name:ident) => {
+        $crate::entry_point!($name, $f, $static_name, {
+            category: Misc,
+            description: "",
+            datasets: [],
+            params: [],
+        });
+    };
+    ($name:expr, $f:expr, { $($meta:tt)* }) => {
+        $crate::entry_point!($name, $f, _ENTRY_POINT, { $($meta)* });
+    };
+    ($name:expr, $f:expr, $static_name:ident, {
+        category: $category:ident,
+        description: $description:expr,
+        datasets: [$($dataset:expr),* $(,)?],
+        params: [$($param:expr => $explanation:expr),* $(,)?] $(,)?
+    }) => {
         #[linkme::distributed_slice($crate::ENTRY_POINTS)]
-        static $static_name: (&'static str, fn()) = ($name, $f);
+        static $static_name: $crate::entry::EntryPoint = $crate::entry::EntryPoint {
+            name: $name,
+            run: $f,
+            category: $crate::entry::Category::$category,
+            description: $description,
+            datasets: &[$($dataset),*],
+            params: &[$(($param, $explanation)),*],
+        };
     };
 }
 
 fn ensure_entry_points_unique() {
-    for (i, (name, _)) in ENTRY_POINTS.iter().enumerate() {
-        for (name2, _) in &ENTRY_POINTS[..i] {
-            assert_ne!(name, name2, "duplicate entry point names");
+    for (i, entry) in ENTRY_POINTS.iter().enumerate() {
+        for entry2 in &ENTRY_POINTS[..i] {
+            assert_ne!(entry.name, entry2.name, "duplicate entry point names");
         }
+        assert!(
+            !COMMANDS.contains(&entry.name),
+            "entry point {:?} is shadowed by a command",
+            entry.name
+        );
     }
 }
 
@@ -88,25 +154,310 @@ fn entry_points_unique() {
     ensure_entry_points_unique();
 }
 
+fn find_entry_point(name: &str) -> Option<&'static EntryPoint> {
+    ENTRY_POINTS.iter().find(|e| e.name == name)
+}
+
+fn run_bench(
+    entry: &EntryPoint,
+    argv: impl Iterator<Item = String>,
+    format: cli::Format,
+) -> Result<(), cli::Error> {
+    let options = bench::BenchOptions::parse(argv)?;
+    let args = cli::Args::parse(options.entry_args.clone())?;
+    dataset::check_inputs(entry, &args)?;
+    let report = bench::bench(entry, &options)?;
+    match &options.json {
+        Some(path) if path.as_os_str() == "-" => {
+            println!("{}", serde_json::to_string_pretty(&report)?)
+        }
+        Some(path) => {
+            std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+            emit(
+                format,
+                || report.to_string(),
+                || serde_json::to_value(&report),
+            )?;
+        }
+        None => emit(
+            format,
+            || report.to_string(),
+            || serde_json::to_value(&report),
+        )?,
+    }
+    Ok(())
+}
+
+fn run_many(
+    entries: &[&'static EntryPoint],
+    argv: impl Iterator<Item = String>,
+    format: cli::Format,
+) -> Result<(), cli::Error> {
+    if entries.is_empty() {
+        return Err("nothing to run".into());
+    }
+    let options = runner::RunOptions::parse(argv)?;
+    let reports = runner::run(entries, &options)?;
+    emit(
+        format,
+        || runner::summary(&reports, options.show_output),
+        || {
+            Ok(serde_json::Value::Array(
+                reports.iter().map(runner::RunReport::to_json).collect(),
+            ))
+        },
+    )?;
+    let code = if reports.iter().all(|r| r.is_ok()) {
+        0
+    } else {
+        1
+    };
+    std::process::exit(code);
+}
+
+// `data` shows what's there, `data extract` unpacks the given archives (all of data/*.tar
+// by default) and shows it again.
+fn data_command(
+    mut argv: impl Iterator<Item = String>,
+    format: cli::Format,
+) -> Result<(), cli::Error> {
+    let dir = dataset::data_root()?;
+    match argv.next().as_deref() {
+        None => {}
+        Some("extract") => {
+            let mut archives: Vec<std::path::PathBuf> = argv.map(Into::into).collect();
+            if archives.is_empty() {
+                archives = dataset::archives_in(&dir)?;
+            }
+            if archives.is_empty() {
+                return Err(format!("no .tar archives in {}", dir.display()).into());
+            }
+            for archive in archives {
+                let extracted = dataset::extract_tar(&archive, &dir)?;
+                eprintln!("{}: extracted {:?}", archive.display(), extracted);
+            }
+        }
+        Some(other) => return Err(format!("unknown data subcommand {:?}", other).into()),
+    }
+    let report = dataset::report(&dir, &ENTRY_POINTS)?;
+    emit(
+        format,
+        || report.to_string(),
+        || serde_json::to_value(&report),
+    )
+}
+
+// `convert IN OUT [--from FORMAT] [--to FORMAT] [--lenient]`, formats otherwise go by extension
+// or contents.
+fn convert_command(argv: impl Iterator<Item = String>) -> Result<(), cli::Error> {
+    use graph::formats::{self, Format};
+    use graph::parse::{self, Mode};
+    let (mut paths, mut from, mut to, mut mode) = (Vec::new(), None, None, Mode::Strict);
+    let mut argv = argv;
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--lenient" => mode = Mode::Lenient,
+            "--from" | "--to" => {
+                let format: Format = argv
+                    .next()
+                    .ok_or_else(|| format!("{} needs a format", arg))?
+                    .parse()?;
+                if arg == "--from" {
+                    from = Some(format);
+                } else {
+                    to = Some(format);
+                }
+            }
+            _ => paths.push(std::path::PathBuf::from(arg)),
+        }
+    }
+    let (input, output) = match paths.as_slice() {
+        [input, output] => (input, output),
+        _ => return Err("usage: convert IN OUT [--from FORMAT] [--to FORMAT] [--lenient]".into()),
+    };
+    let (from, data, warnings) = formats::load::<String>(input, from, mode)?;
+    parse::report(&warnings);
+    let to = formats::save(&data, output, to)?;
+    eprintln!(
+        "{} ({}) -> {} ({}): {} nodes, {} edges",
+        input.display(),
+        from,
+        output.display(),
+        to,
+        data.nodes.len(),
+        data.edges.len()
+    );
+    Ok(())
+}
+
+fn print_usage() {
+    eprintln!("usage:");
+    eprintln!("  cargo run <entry point> [--input FILE] [--seed N] [--iterations N] [ARGS...]");
+    eprintln!("add --format json to any of these for machine readable output, and");
+    eprintln!(
+        "--data-root DIR (or ${}) to read the course files from DIR",
+        dataset::DATA_ROOT_VAR
+    );
+    eprintln!("  cargo run list [CATEGORY]");
+    eprintln!("  cargo run help <entry point>");
+    eprintln!("  cargo run verify [ENTRY POINT...]");
+    eprintln!("  cargo run bench <entry point> [--runs N] [--warmup M] [--json FILE|-] [ARGS...]");
+    eprintln!("  cargo run run a,b,c [--jobs N] [--timeout SECS] [--show-output] [ARGS...]");
+    eprintln!("  cargo run run-all [--jobs N] [--timeout SECS] [--show-output] [ARGS...]");
+    eprintln!("  cargo run data [extract [ARCHIVE.tar...]]");
+    eprintln!("  cargo run generate integers|karger|dijkstra|scc [--seed N] [--size N] [--output FILE] ...");
+    eprintln!("  cargo run convert IN OUT [--from FORMAT] [--to FORMAT] [--lenient]   (dot, dimacs, graphml, edges, adjacency, weighted)");
+}
+
+// Prints `text` or `json` depending on --format; `json` is only computed when asked for.
+fn emit(
+    format: cli::Format,
+    text: impl FnOnce() -> String,
+    json: impl FnOnce() -> serde_json::Result<serde_json::Value>,
+) -> Result<(), cli::Error> {
+    match format {
+        cli::Format::Text => {
+            let text = text();
+            if !text.is_empty() {
+                println!("{}", text.trim_end());
+            }
+        }
+        cli::Format::Json => println!("{}", serde_json::to_string_pretty(&json()?)?),
+    }
+    Ok(())
+}
+
 fn main() {
     ensure_entry_points_unique();
+    runner::serve_child(ENTRY_POINTS.iter());
 
-    if let Some(entry_point) = std::env::args().nth(1) {
-        let p = ENTRY_POINTS.iter().find(|(name, _)| name == &entry_point);
-        if let Some((_, f)) = p {
-            f();
-            return;
-        } else {
-            eprintln!("no entry point {:?}", entry_point);
+    let (globals, argv) = match cli::take_globals(std::env::args().skip(1).collect()) {
+        Ok(split) => split,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
         }
-    } else {
-        eprintln!("entry point not specified");
-        eprintln!("usage:");
-        eprintln!("  cargo run <entry point>");
+    };
+    let format = globals.format;
+    if let Some(dir) = globals.data_root {
+        dataset::set_data_root(dir);
     }
-    eprintln!("possible entry points:");
-    for (name, _) in ENTRY_POINTS {
-        eprintln!("- {}", name);
+    let mut argv = argv.into_iter();
+    let result = match argv.next().as_deref() {
+        Some("list") => match argv.next().map(|c| c.parse::<Category>()).transpose() {
+            Ok(only) => emit(
+                format,
+                || entry::listing(&ENTRY_POINTS, only),
+                || {
+                    Ok(serde_json::Value::Array(
+                        ENTRY_POINTS
+                            .iter()
+                            .filter(|e| only.is_none() || only == Some(e.category))
+                            .map(EntryPoint::to_json)
+                            .collect(),
+                    ))
+                },
+            ),
+            Err(e) => Err(e.into()),
+        },
+        Some("help") => match argv.next() {
+            Some(name) => match find_entry_point(&name) {
+                Some(entry) => emit(format, || entry.help(), || Ok(entry.to_json())),
+                None => Err(format!("no entry point {:?}", name).into()),
+            },
+            None => {
+                print_usage();
+                eprintln!();
+                eprint!("{}", entry::listing(&ENTRY_POINTS, None));
+                Ok(())
+            }
+        },
+        Some("verify") => {
+            let only: Vec<String> = argv.collect();
+            let outcomes = match dataset::data_root() {
+                Ok(dir) => verify::verify(&ENTRY_POINTS, &only, &dir),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            emit(
+                format,
+                || verify::table(&outcomes),
+                || Ok(verify::to_json(&outcomes)),
+            )
+            .and_then(|()| {
+                if outcomes.iter().any(|o| o.is_failure()) {
+                    Err("some answers are wrong".into())
+                } else {
+                    Ok(())
+                }
+            })
+        }
+        Some("data") => data_command(argv, format),
+        Some("generate") => generate::GenerateOptions::parse(argv).and_then(|options| {
+            let generated = generate::generate(&options)?;
+            if options.output.is_some() {
+                emit(
+                    format,
+                    || generated.to_string(),
+                    || serde_json::to_value(&generated),
+                )
+            } else {
+                // stdout is the data itself
+                eprintln!("{}", generated);
+                Ok(())
+            }
+        }),
+        Some("convert") => convert_command(argv),
+        Some("bench") => {
+            let name = argv.next().unwrap_or_default();
+            match find_entry_point(&name) {
+                Some(entry) => run_bench(entry, argv, format),
+                None => Err(format!("no entry point {:?}", name).into()),
+            }
+        }
+        Some("run") => {
+            let names = argv.next().unwrap_or_default();
+            let entries: Result<Vec<&'static EntryPoint>, cli::Error> = names
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(|name| {
+                    find_entry_point(name)
+                        .ok_or_else(|| format!("no entry point {:?}", name).into())
+                })
+                .collect();
+            entries.and_then(|entries| run_many(&entries, argv, format))
+        }
+        Some("run-all") => {
+            let entries: Vec<&'static EntryPoint> = ENTRY_POINTS.iter().collect();
+            run_many(&entries, argv, format)
+        }
+        Some(name) => match find_entry_point(name) {
+            Some(entry) => cli::Args::parse(argv)
+                .map_err(cli::Error::from)
+                .and_then(|args| {
+                    dataset::check_inputs(entry, &args)?;
+                    (entry.run)(&args)
+                })
+                .and_then(|answer| emit(format, || answer.to_string(), || answer.to_json())),
+            None => {
+                eprintln!("no entry point {:?}", name);
+                eprintln!("possible entry points:");
+                eprint!("{}", entry::listing(&ENTRY_POINTS, None));
+                std::process::exit(1);
+            }
+        },
+        None => {
+            eprintln!("entry point not specified");
+            print_usage();
+            eprintln!("possible entry points:");
+            eprint!("{}", entry::listing(&ENTRY_POINTS, None));
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
     }
-    std::process::exit(1);
 }
@@ -0,0 +1,481 @@
+use crate::cli;
+use crate::dataset;
+use crate::entry::EntryPoint;
+use fxhash::FxHashMap as HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::process::{self, Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// `run`/`run-all`'s own flags; whatever it doesn't recognise goes to every entry point.
+#[derive(Debug, PartialEq)]
+pub struct RunOptions {
+    pub jobs: usize,
+    pub timeout: Duration,
+    // Print captured output of successful runs too, not just of the broken ones
+    pub show_output: bool,
+    pub entry_args: Vec<String>,
+}
+
+impl RunOptions {
+    pub fn parse<I: IntoIterator<Item = String>>(argv: I) -> Result<Self, cli::ArgsError> {
+        let mut options = RunOptions {
+            jobs: thread::available_parallelism().map_or(1, |n| n.get()),
+            timeout: Duration::from_secs(600),
+            show_output: false,
+            entry_args: Vec::new(),
+        };
+        let mut argv = argv.into_iter();
+        while let Some(arg) = argv.next() {
+            let mut value = |flag: &str| {
+                let value = argv
+                    .next()
+                    .ok_or_else(|| cli::ArgsError::MissingValue(flag.to_string()))?;
+                match value.parse::<f64>() {
+                    Ok(x) if x > 0.0 && x.is_finite() => Ok(x),
+                    _ => Err(cli::ArgsError::InvalidValue {
+                        flag: flag.to_string(),
+                        value,
+                    }),
+                }
+            };
+            match arg.as_str() {
+                "--jobs" => options.jobs = value(&arg)?.ceil() as usize,
+                "--timeout" => options.timeout = Duration::from_secs_f64(value(&arg)?),
+                "--show-output" => options.show_output = true,
+                _ => options.entry_args.push(arg),
+            }
+        }
+        Ok(options)
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub enum RunStatus {
+    Ok,
+    Failed(String),
+    Panicked(String),
+    // Still running when the time was up, so it was killed
+    TimedOut,
+}
+
+pub struct RunReport {
+    pub entry: &'static str,
+    pub status: RunStatus,
+    pub elapsed: Duration,
+    pub answer: Option<Value>,
+    // Everything the entry point printed, stdout and stderr interleaved like `cargo test` does
+    pub output: String,
+}
+
+impl RunReport {
+    pub fn is_ok(&self) -> bool {
+        self.status == RunStatus::Ok
+    }
+
+    pub fn to_json(&self) -> Value {
+        let (status, detail) = match &self.status {
+            RunStatus::Ok => ("ok", Value::Null),
+            RunStatus::Failed(e) => ("failed", Value::String(e.clone())),
+            RunStatus::Panicked(e) => ("panicked", Value::String(e.clone())),
+            RunStatus::TimedOut => ("timed out", Value::Null),
+        };
+        json!({
+            "entry": self.entry,
+            "status": status,
+            "detail": detail,
+            "elapsed_ms": self.elapsed.as_secs_f64() * 1000.0,
+            "answer": self.answer,
+            "output": self.output,
+        })
+    }
+}
+
+// Each run is a child process: this executable again, with `CHILD_VAR` saying what to run and
+// where to leave the outcome. Its stdout and stderr go to the same file, so they interleave
+// like `cargo test` shows them, and a run that goes over the timeout can simply be killed.
+const CHILD_VAR: &str = "LEARNING_RUNNER_CHILD";
+
+// How often to look in on the children
+const POLL: Duration = Duration::from_millis(10);
+
+// Runs so far in this process, to keep their files apart
+static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Serialize, Deserialize)]
+struct ChildRequest {
+    entry: String,
+    args: Vec<String>,
+    output: PathBuf,
+    outcome: PathBuf,
+}
+
+// What the child writes to `outcome` on its way out
+#[derive(Serialize, Deserialize)]
+struct Outcome {
+    status: RunStatus,
+    answer: Option<Value>,
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "panicked with a non-string payload".to_string()
+    }
+}
+
+// Runs one entry point right here; only ever called in a child.
+fn run_here(entry: &EntryPoint, argv: Vec<String>) -> (RunStatus, Option<Value>) {
+    let args = match cli::Args::parse(argv) {
+        Ok(args) => args,
+        Err(e) => return (RunStatus::Failed(e.to_string()), None),
+    };
+    match panic::catch_unwind(AssertUnwindSafe(|| (entry.run)(&args))) {
+        Ok(Ok(answer)) => match answer.to_json() {
+            Ok(json) => (RunStatus::Ok, Some(json)),
+            Err(e) => (RunStatus::Failed(e.to_string()), None),
+        },
+        Ok(Err(e)) => (RunStatus::Failed(e.to_string()), None),
+        Err(payload) => (RunStatus::Panicked(panic_message(payload)), None),
+    }
+}
+
+/// If `run` started this process, runs the entry point it asked for and exits; otherwise
+/// returns straight away. `main` calls this before it looks at its arguments.
+pub fn serve_child<'a>(entries: impl IntoIterator<Item = &'a EntryPoint>) {
+    let request: ChildRequest = match std::env::var(CHILD_VAR) {
+        Ok(request) => serde_json::from_str(&request).expect("a malformed runner request"),
+        Err(_) => return,
+    };
+    // Whatever was printed before we got here (a test harness's banner, say) isn't the entry
+    // point's. The file was opened for appending, so the output starts over from the top.
+    let _ = io::stdout().flush();
+    if let Ok(file) = OpenOptions::new().write(true).open(&request.output) {
+        let _ = file.set_len(0);
+    }
+    let (status, answer) = match entries.into_iter().find(|e| e.name == request.entry) {
+        Some(entry) => run_here(entry, request.args),
+        None => (
+            RunStatus::Failed(format!("no entry point {:?}", request.entry)),
+            None,
+        ),
+    };
+    let _ = io::stdout().flush();
+    let outcome = serde_json::to_vec(&Outcome { status, answer }).expect("an answer as JSON");
+    // Without the outcome file the parent reports the exit status instead
+    process::exit(match fs::write(&request.outcome, outcome) {
+        Ok(()) => 0,
+        Err(_) => 1,
+    })
+}
+
+// The executable that runs entry points. Under `cargo test` that's the test binary, which
+// takes the request through the `serve` test.
+#[cfg(not(test))]
+fn child_command() -> io::Result<Command> {
+    Ok(Command::new(std::env::current_exe()?))
+}
+
+#[cfg(test)]
+fn child_command() -> io::Result<Command> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command.args(["runner::tests::serve", "--exact", "--nocapture"]);
+    Ok(command)
+}
+
+struct Running {
+    started: Instant,
+    child: Child,
+    output: PathBuf,
+    outcome: PathBuf,
+}
+
+fn spawn(entry: &EntryPoint, argv: &[String]) -> io::Result<Running> {
+    let run = RUNS.fetch_add(1, Ordering::Relaxed);
+    let base = std::env::temp_dir().join(format!("learning-run-{}-{}", process::id(), run));
+    let (output, outcome) = (base.with_extension("out"), base.with_extension("json"));
+    let _ = fs::remove_file(&outcome);
+    let file = OpenOptions::new().create(true).append(true).open(&output)?;
+    file.set_len(0)?;
+    let request = ChildRequest {
+        entry: entry.name.to_string(),
+        args: argv.to_vec(),
+        output: output.clone(),
+        outcome: outcome.clone(),
+    };
+    let mut command = child_command()?;
+    command
+        .env(CHILD_VAR, serde_json::to_string(&request)?)
+        .stdin(Stdio::null())
+        .stdout(file.try_clone()?)
+        .stderr(file);
+    // --data-root isn't passed on, but the same directory is through the variable
+    if let Ok(dir) = dataset::data_root() {
+        command.env(dataset::DATA_ROOT_VAR, dir);
+    }
+    Ok(Running {
+        started: Instant::now(),
+        child: command.spawn()?,
+        output,
+        outcome,
+    })
+}
+
+// Reads back what a finished child left behind and cleans up after it. No exit status means
+// it was killed for taking too long.
+fn finish(entry: &'static str, run: Running, exit: Option<ExitStatus>) -> RunReport {
+    let output = fs::read(&run.output)
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+        .unwrap_or_default();
+    let outcome = fs::read(&run.outcome)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Outcome>(&bytes).ok());
+    let (status, answer) = match (exit, outcome) {
+        (None, _) => (RunStatus::TimedOut, None),
+        (Some(_), Some(outcome)) => (outcome.status, outcome.answer),
+        (Some(exit), None) => (
+            RunStatus::Failed(format!("exited without an answer ({})", exit)),
+            None,
+        ),
+    };
+    let _ = fs::remove_file(&run.output);
+    let _ = fs::remove_file(&run.outcome);
+    RunReport {
+        entry,
+        status,
+        elapsed: run.started.elapsed(),
+        answer,
+        output,
+    }
+}
+
+// Runs the entry points, up to `options.jobs` child processes at a time. Reports come back in
+// the order the entry points were given.
+pub fn run(
+    entries: &[&'static EntryPoint],
+    options: &RunOptions,
+) -> Result<Vec<RunReport>, cli::ArgsError> {
+    let args = cli::Args::parse(options.entry_args.clone())?;
+    let mut queue = entries.iter().copied().enumerate();
+    let mut running: HashMap<usize, Running> = HashMap::default();
+    let mut reports: Vec<Option<RunReport>> = entries.iter().map(|_| None).collect();
+    loop {
+        while running.len() < options.jobs.max(1) {
+            let (i, entry) = match queue.next() {
+                Some(next) => next,
+                None => break,
+            };
+            // Checked up front like a single run is, so a missing dataset is a failure
+            // rather than a panic somewhere in the loader
+            let started = Instant::now();
+            let spawned = dataset::check_inputs(entry, &args).and_then(|()| {
+                spawn(entry, &options.entry_args)
+                    .map_err(|e| format!("couldn't start a process for it: {}", e).into())
+            });
+            match spawned {
+                Ok(run) => {
+                    running.insert(i, run);
+                }
+                Err(e) => {
+                    reports[i] = Some(RunReport {
+                        entry: entry.name,
+                        status: RunStatus::Failed(e.to_string()),
+                        elapsed: started.elapsed(),
+                        answer: None,
+                        output: String::new(),
+                    })
+                }
+            }
+        }
+        if running.is_empty() {
+            break;
+        }
+        let before = running.len();
+        let indices: Vec<usize> = running.keys().copied().collect();
+        for i in indices {
+            let run = running.get_mut(&i).unwrap();
+            let exit = match run.child.try_wait() {
+                Ok(Some(exit)) => Some(exit),
+                _ if run.started.elapsed() < options.timeout => continue,
+                _ => {
+                    let _ = run.child.kill();
+                    let _ = run.child.wait();
+                    None
+                }
+            };
+            let run = running.remove(&i).unwrap();
+            reports[i] = Some(finish(entries[i].name, run, exit));
+        }
+        if running.len() == before {
+            thread::sleep(POLL);
+        }
+    }
+    Ok(reports.into_iter().map(Option::unwrap).collect())
+}
+
+pub fn summary(reports: &[RunReport], show_output: bool) -> String {
+    let width = reports.iter().map(|r| r.entry.len()).max().unwrap_or(0);
+    let mut summary = String::new();
+    for r in reports {
+        if (show_output || !r.is_ok()) && !r.output.is_empty() {
+            summary.push_str(&format!("---- {} output ----\n{}", r.entry, r.output));
+            if !r.output.ends_with('\n') {
+                summary.push('\n');
+            }
+        }
+    }
+    for r in reports {
+        let status = match &r.status {
+            RunStatus::Ok => "ok".to_string(),
+            RunStatus::Failed(e) => format!("FAILED {}", e),
+            RunStatus::Panicked(e) => format!("PANICKED {}", e),
+            RunStatus::TimedOut => "TIMED OUT".to_string(),
+        };
+        summary.push_str(&format!(
+            "{:width$}  {:>9.3}s  {}\n",
+            r.entry,
+            r.elapsed.as_secs_f64(),
+            status,
+            width = width
+        ));
+    }
+    let ok = reports.iter().filter(|r| r.is_ok()).count();
+    summary.push_str(&format!(
+        "{} ok, {} not ok, exit status {}\n",
+        ok,
+        reports.len() - ok,
+        if ok == reports.len() { 0 } else { 1 }
+    ));
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::{Answer, Category};
+
+    fn chatty(_args: &cli::Args) -> Result<Box<dyn Answer>, cli::Error> {
+        println!("to stdout");
+        eprintln!("to stderr");
+        Ok(Box::new(42))
+    }
+
+    fn broken(_args: &cli::Args) -> Result<Box<dyn Answer>, cli::Error> {
+        Err("no such file".into())
+    }
+
+    fn explodes(_args: &cli::Args) -> Result<Box<dyn Answer>, cli::Error> {
+        panic!("Impossible happened!");
+    }
+
+    fn sleepy(_args: &cli::Args) -> Result<Box<dyn Answer>, cli::Error> {
+        println!("zzz");
+        thread::sleep(Duration::from_secs(5));
+        Ok(Box::new(0))
+    }
+
+    macro_rules! fake_entry {
+        ($static_name:ident, $name:expr, $f:expr) => {
+            fake_entry!($static_name, $name, $f, &[]);
+        };
+        ($static_name:ident, $name:expr, $f:expr, $datasets:expr) => {
+            static $static_name: EntryPoint = EntryPoint {
+                name: $name,
+                run: $f,
+                category: Category::Misc,
+                description: "",
+                datasets: $datasets,
+                params: &[],
+            };
+        };
+    }
+
+    fake_entry!(CHATTY, "chatty", chatty);
+    fake_entry!(BROKEN, "broken", broken);
+    fake_entry!(EXPLODES, "explodes", explodes);
+    fake_entry!(SLEEPY, "sleepy", sleepy);
+    fake_entry!(UNFED, "unfed", explodes, &["no-such-dataset.txt"]);
+
+    // Where the runs above happen: `run` starts this test binary again with just this test,
+    // and `serve_child` takes it from there. Started any other way, there's nothing to do.
+    #[test]
+    fn serve() {
+        serve_child(vec![&CHATTY, &BROKEN, &EXPLODES, &SLEEPY, &UNFED]);
+    }
+
+    fn options(argv: &[&str]) -> RunOptions {
+        RunOptions::parse(argv.iter().map(|x| x.to_string())).unwrap()
+    }
+
+    #[test]
+    fn runs_are_isolated() {
+        let reports = run(
+            &[&CHATTY, &BROKEN, &EXPLODES, &SLEEPY],
+            &options(&["--jobs", "4", "--timeout", "0.5"]),
+        )
+        .unwrap();
+        let names: Vec<&str> = reports.iter().map(|r| r.entry).collect();
+        assert_eq!(names, vec!["chatty", "broken", "explodes", "sleepy"]);
+
+        assert_eq!(reports[0].status, RunStatus::Ok);
+        assert_eq!(reports[0].answer, Some(json!(42)));
+        assert_eq!(reports[0].output, "to stdout\nto stderr\n");
+
+        assert_eq!(
+            reports[1].status,
+            RunStatus::Failed("no such file".to_string())
+        );
+        assert_eq!(
+            reports[2].status,
+            RunStatus::Panicked("Impossible happened!".to_string())
+        );
+        assert!(reports[2].output.contains("Impossible happened!"));
+        assert_eq!(reports[3].status, RunStatus::TimedOut);
+        assert_eq!(reports[3].output, "zzz\n");
+
+        // Killed, not left running: it would have printed this after the sleep
+        assert!(reports[3].elapsed < Duration::from_secs(4));
+
+        let summary = summary(&reports, false);
+        assert!(summary.contains("1 ok, 3 not ok, exit status 1"));
+        assert!(!summary.contains("to stdout"));
+    }
+
+    #[test]
+    fn one_job_at_a_time() {
+        let reports = run(&[&CHATTY, &BROKEN, &CHATTY], &options(&["--jobs", "1"])).unwrap();
+        assert_eq!(reports.len(), 3);
+        assert!(reports[0].is_ok() && !reports[1].is_ok() && reports[2].is_ok());
+        assert_eq!(reports[2].to_json()["output"], "to stdout\nto stderr\n");
+    }
+
+    #[test]
+    fn missing_datasets_fail_before_starting() {
+        let reports = run(&[&UNFED, &CHATTY], &options(&[])).unwrap();
+        // Not there, or no data root at all; either way it never ran
+        assert!(matches!(reports[0].status, RunStatus::Failed(_)));
+        assert!(reports[0].output.is_empty());
+        assert!(reports[1].is_ok());
+        // An --input of its own means there's nothing to check
+        let reports = run(&[&UNFED], &options(&["--input", "elsewhere.txt"])).unwrap();
+        assert!(matches!(reports[0].status, RunStatus::Panicked(_)));
+    }
+
+    #[test]
+    fn options_split_from_entry_args() {
+        let options = options(&["--seed", "1", "--timeout", "2.5", "--show-output"]);
+        assert_eq!(options.timeout, Duration::from_millis(2500));
+        assert!(options.show_output);
+        assert_eq!(options.entry_args, vec!["--seed", "1"]);
+        assert!(RunOptions::parse(vec!["--jobs".to_string(), "0".to_string()]).is_err());
+    }
+}
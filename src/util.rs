@@ -1,30 +1,119 @@
 #![allow(dead_code)]
 
+use std::fmt;
 use std::path::{Path, PathBuf};
 
-pub fn project_root() -> PathBuf {
-    let exe = std::fs::canonicalize(std::env::args().next().unwrap()).unwrap();
-    let mut path: &Path = &exe;
-    while !((path.file_name().unwrap() == "competitive" && path.is_dir())
-        || path.clone().join(".git").is_dir())
+/// No project root above the executable or the current directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootNotFound {
+    // Every directory that was checked, in order
+    pub tried: Vec<PathBuf>,
+}
+
+impl fmt::Display for RootNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "project root not found (looked for .git in")?;
+        for dir in &self.tried {
+            write!(f, "\n  {}", dir.display())?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl std::error::Error for RootNotFound {}
+
+fn is_root(dir: &Path) -> bool {
     // For those who clone to weird places
-    {
-        path = path.parent().unwrap();
+    (dir.file_name().map(|n| n == "competitive").unwrap_or(false) && dir.is_dir())
+        || dir.join(".git").is_dir()
+}
+
+// Walks up from each of `starts` until a directory looks like the repo.
+fn find_root(starts: &[PathBuf]) -> Result<PathBuf, RootNotFound> {
+    let mut tried = Vec::new();
+    for start in starts {
+        for dir in start.ancestors() {
+            if tried.iter().any(|t: &PathBuf| t == dir) {
+                // Already walked from here up via an earlier start
+                break;
+            }
+            if is_root(dir) {
+                return Ok(dir.to_owned());
+            }
+            tried.push(dir.to_owned());
+        }
+    }
+    Err(RootNotFound { tried })
+}
+
+// The repo this binary was built from: found above the executable (target/...), or failing
+// that above the current directory.
+pub fn project_root() -> Result<PathBuf, RootNotFound> {
+    let mut starts = Vec::new();
+    if let Ok(exe) = std::env::current_exe().and_then(std::fs::canonicalize) {
+        starts.extend(exe.parent().map(Path::to_owned));
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        starts.push(cwd);
     }
-    path.to_owned()
+    find_root(&starts)
 }
 
-pub fn project_path(rel: impl AsRef<Path>) -> PathBuf {
+pub fn project_path(rel: impl AsRef<Path>) -> Result<PathBuf, RootNotFound> {
     // Can't simply return project_root().join(rel)
     // Need to deal with forward and backward slashes on Windows.
-    let mut result = project_root();
+    let mut result = project_root()?;
     for part in rel.as_ref().iter() {
         result.push(part);
     }
-    result
+    Ok(result)
+}
+
+/// A file in the temp dir for tests that have to hand over a path, say to an entry point's
+/// `--input`. It goes away on drop, so a failing assert doesn't leave it behind.
+#[cfg(test)]
+pub struct TempFile {
+    pub path: PathBuf,
+}
+
+#[cfg(test)]
+impl TempFile {
+    // `name` keeps tests running at the same time apart
+    pub fn new(name: &str, contents: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        TempFile { path }
+    }
+
+    pub fn write(&self, contents: &str) {
+        std::fs::write(&self.path, contents).unwrap();
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
 }
 
 #[test]
 fn project_path_test() {
-    assert!(project_path("src/util.rs").exists());
+    assert!(project_path("src/util.rs").unwrap().exists());
+}
+
+#[test]
+fn root_not_found_lists_every_directory() {
+    let dir = std::env::temp_dir().join(format!("no-root-{}", std::process::id()));
+    let deep = dir.join("a").join("b");
+    std::fs::create_dir_all(&deep).unwrap();
+    // Somebody may well keep a git repo above the temp dir, in which case there's no error
+    if let Err(e) = find_root(&[deep.clone(), dir.join("a")]) {
+        assert_eq!(e.tried[0], deep);
+        assert_eq!(e.tried[1], dir.join("a"));
+        assert!(e.tried.last().unwrap().parent().is_none());
+        assert_eq!(e.tried.iter().filter(|t| **t == dir).count(), 1);
+        assert!(e.to_string().contains(&deep.display().to_string()));
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
 }
@@ -0,0 +1,241 @@
+use crate::cli;
+use crate::entry::EntryPoint;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Known-good answer of one entry point on one dataset.
+pub struct Expected {
+    pub entry: &'static str,
+    pub dataset: &'static str,
+    // Extra flags, mostly to make randomised entry points quick and reproducible
+    pub args: &'static [&'static str],
+    // Only the fields mentioned here are compared, so answers can grow extra fields freely.
+    pub answer: fn() -> Value,
+}
+
+pub const EXPECTED: &[Expected] = &[
+    Expected {
+        entry: "inversions",
+        dataset: "IntegerArray.txt",
+        args: &[],
+        answer: || json!({ "inversions": 2407905288u64 }),
+    },
+    Expected {
+        entry: "comparisons",
+        dataset: "QuickSort.txt",
+        args: &[],
+        answer: || json!({ "first": 162085, "last": 164123, "median": 138382 }),
+    },
+    Expected {
+        entry: "karger",
+        dataset: "kargerMinCut.txt",
+        args: &["--seed", "1", "--iterations", "300"],
+        answer: || json!({ "min_cut": 17 }),
+    },
+    Expected {
+        entry: "karger",
+        dataset: "kargerTriangle.txt",
+        args: &["--seed", "1"],
+        answer: || json!({ "min_cut": 2 }),
+    },
+    Expected {
+        entry: "karger",
+        dataset: "kargerEnvelopes.txt",
+        args: &["--seed", "1"],
+        answer: || json!({ "min_cut": 2 }),
+    },
+    Expected {
+        entry: "kosajaru",
+        dataset: "SCC.txt",
+        args: &[],
+        answer: || json!({ "sizes": [434821, 968, 459, 313, 211] }),
+    },
+    Expected {
+        entry: "dijkstra",
+        dataset: "dijkstraData.txt",
+        args: &[],
+        answer: || {
+            json!({
+                "from": 1,
+                "distances": {
+                    "7": 2599, "37": 2610, "59": 2947, "82": 2052, "99": 2367,
+                    "115": 2399, "133": 2029, "165": 2442, "188": 2505, "197": 3068
+                }
+            })
+        },
+    },
+    Expected {
+        entry: "2sum_",
+        dataset: "algo1-programming_prob-2sum.txt",
+        args: &[],
+        answer: || json!({ "targets": 427 }),
+    },
+    Expected {
+        entry: "median_",
+        dataset: "Median.txt",
+        args: &[],
+        answer: || json!({ "sum_mod_10000": 1213 }),
+    },
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    Pass,
+    Fail { got: Value },
+    Error(String),
+    // Dataset isn't on disk, so there's nothing to check
+    Skipped,
+}
+
+pub struct Outcome {
+    pub entry: &'static str,
+    pub dataset: &'static str,
+    pub status: Status,
+    pub elapsed: Duration,
+}
+
+impl Outcome {
+    pub fn is_failure(&self) -> bool {
+        matches!(self.status, Status::Fail { .. } | Status::Error(_))
+    }
+}
+
+// `got` matches `expected` if every field of `expected` is in `got` with a matching value.
+pub fn answer_matches(expected: &Value, got: &Value) -> bool {
+    match (expected, got) {
+        (Value::Object(expected), Value::Object(got)) => expected
+            .iter()
+            .all(|(k, v)| matches!(got.get(k), Some(g) if answer_matches(v, g))),
+        _ => expected == got,
+    }
+}
+
+pub fn verify_one(entry_points: &[EntryPoint], expected: &Expected, data_dir: &Path) -> Outcome {
+    let start = Instant::now();
+    let input = data_dir.join(expected.dataset);
+    let status = match entry_points.iter().find(|e| e.name == expected.entry) {
+        None => Status::Error(format!("no entry point {:?}", expected.entry)),
+        Some(_) if !input.exists() => Status::Skipped,
+        Some(entry) => {
+            let argv = vec!["--input".to_string(), input.display().to_string()]
+                .into_iter()
+                .chain(expected.args.iter().map(|a| a.to_string()));
+            let answer = cli::Args::parse(argv)
+                .map_err(cli::Error::from)
+                .and_then(|args| (entry.run)(&args))
+                .and_then(|answer| answer.to_json().map_err(cli::Error::from));
+            match answer {
+                Ok(got) if answer_matches(&(expected.answer)(), &got) => Status::Pass,
+                Ok(got) => Status::Fail { got },
+                Err(e) => Status::Error(e.to_string()),
+            }
+        }
+    };
+    Outcome {
+        entry: expected.entry,
+        dataset: expected.dataset,
+        status,
+        elapsed: start.elapsed(),
+    }
+}
+
+// Runs every expectation, or only those for the entry points named in `only`.
+pub fn verify(entry_points: &[EntryPoint], only: &[String], data_dir: &Path) -> Vec<Outcome> {
+    EXPECTED
+        .iter()
+        .filter(|e| only.is_empty() || only.iter().any(|o| o == e.entry))
+        .map(|e| verify_one(entry_points, e, data_dir))
+        .collect()
+}
+
+pub fn to_json(outcomes: &[Outcome]) -> Value {
+    Value::Array(
+        outcomes
+            .iter()
+            .map(|o| {
+                let (status, detail) = match &o.status {
+                    Status::Pass => ("pass", Value::Null),
+                    Status::Fail { got } => ("fail", got.clone()),
+                    Status::Error(e) => ("error", Value::String(e.clone())),
+                    Status::Skipped => ("skipped", Value::Null),
+                };
+                json!({
+                    "entry": o.entry,
+                    "dataset": o.dataset,
+                    "status": status,
+                    "detail": detail,
+                    "elapsed_ms": o.elapsed.as_secs_f64() * 1000.0,
+                })
+            })
+            .collect(),
+    )
+}
+
+pub fn table(outcomes: &[Outcome]) -> String {
+    let entry_width = outcomes.iter().map(|o| o.entry.len()).max().unwrap_or(0);
+    let dataset_width = outcomes.iter().map(|o| o.dataset.len()).max().unwrap_or(0);
+    let mut table = String::new();
+    for o in outcomes {
+        let status = match &o.status {
+            Status::Pass => "PASS".to_string(),
+            Status::Fail { got } => format!("FAIL got {}", got),
+            Status::Error(e) => format!("ERROR {}", e),
+            Status::Skipped => "SKIP dataset missing".to_string(),
+        };
+        table.push_str(&format!(
+            "{:ew$}  {:dw$}  {:>9.3}s  {}\n",
+            o.entry,
+            o.dataset,
+            o.elapsed.as_secs_f64(),
+            status,
+            ew = entry_width,
+            dw = dataset_width
+        ));
+    }
+    let count = |f: fn(&Status) -> bool| outcomes.iter().filter(|o| f(&o.status)).count();
+    table.push_str(&format!(
+        "{} passed, {} failed, {} skipped\n",
+        count(|s| *s == Status::Pass),
+        outcomes.iter().filter(|o| o.is_failure()).count(),
+        count(|s| *s == Status::Skipped),
+    ));
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_subsets() {
+        let got = json!({ "min_cut": 17, "iterations": 300 });
+        assert!(answer_matches(&json!({ "min_cut": 17 }), &got));
+        assert!(!answer_matches(&json!({ "min_cut": 2 }), &got));
+        assert!(!answer_matches(&json!({ "max_cut": 17 }), &got));
+        assert!(answer_matches(&json!([1, 2]), &json!([1, 2])));
+        assert!(!answer_matches(&json!([1, 2]), &json!([1, 2, 3])));
+    }
+
+    #[test]
+    fn expectations_point_at_entry_points() {
+        for expected in EXPECTED {
+            assert!(
+                crate::ENTRY_POINTS.iter().any(|e| e.name == expected.entry),
+                "no entry point {:?}",
+                expected.entry
+            );
+        }
+    }
+
+    #[test]
+    fn small_datasets_verify() {
+        for dataset in &["kargerTriangle.txt", "kargerEnvelopes.txt"] {
+            let path = crate::require_dataset!(*dataset);
+            let expected = EXPECTED.iter().find(|e| e.dataset == *dataset).unwrap();
+            let outcome = verify_one(&crate::ENTRY_POINTS, expected, path.parent().unwrap());
+            assert_eq!(outcome.status, Status::Pass, "{}", dataset);
+            assert_eq!(to_json(&[outcome])[0]["status"], "pass");
+        }
+    }
+}